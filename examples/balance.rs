@@ -13,7 +13,7 @@ async fn main() -> anyhow::Result<()> {
 
     let client = AnafClient::new();
 
-    let request = BalanceRequest::new(40914732, 2022);
+    let request = BalanceRequest::new("RO40914732".parse()?, 2022);
     let response = client.balance(BalanceApiVersion::V1).send(request).await?;
 
     dbg!(&response);
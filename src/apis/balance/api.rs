@@ -1,6 +1,9 @@
-use reqwest::{Client, StatusCode};
+use std::sync::Arc;
 
-use crate::{balance::BalanceResponse, ApiError, Result};
+use crate::{
+    balance::BalanceResponse, read_response, ApiFamily, BalanceStore, CallPolicy, CircuitBreaker,
+    HttpRequest, RateLimiter, RawResponse, Result, RetryPolicy, Transport, PAYLOAD_TARGET,
+};
 
 use super::{BalanceApiVersion, BalanceRawResponse, BalanceRequest};
 
@@ -11,57 +14,117 @@ use super::{BalanceApiVersion, BalanceRawResponse, BalanceRequest};
 /// >>**Note**: This API is currently unstable and may change in the future.
 ///
 /// # Example
-/// ```rust
+/// ```rust,no_run
+/// # use anaf_api::{balance::{BalanceApiVersion, BalanceRequest}, AnafClient};
+/// # async fn run() -> anaf_api::Result<()> {
 /// let client = AnafClient::new();
-/// let request = BalanceRequest::new(40914732, 2022);
+/// let request = BalanceRequest::new("RO40914732".parse()?, 2022);
 /// let response = client.balance(BalanceApiVersion::V1).send(request).await?;
 ///
 /// dbg!(&response);
+/// # Ok(())
+/// # }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BalanceApi {
     api_url: String,
     version: BalanceApiVersion,
-    client: Client,
+    policy: CallPolicy,
+    store: Option<Arc<dyn BalanceStore>>,
 }
 
 impl BalanceApi {
-    pub fn new(version: BalanceApiVersion, client: Client, api_url: &str) -> Self {
+    pub fn new(version: BalanceApiVersion, transport: Arc<dyn Transport>, api_url: &str) -> Self {
         Self {
             version,
-            client,
             api_url: api_url.to_owned(),
+            policy: CallPolicy::new(transport),
+            store: None,
         }
     }
+
+    /// Paces the requests of this handle with the given rate limiter.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<RateLimiter>) -> Self {
+        self.policy.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Fails fast while the given circuit breaker is open.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Option<CircuitBreaker>) -> Self {
+        self.policy.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// Retries failed requests of this handle with the given policy.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.policy.retry_policy = retry_policy;
+        self
+    }
+
+    /// Serves the balances found in the given store without asking ANAF again.
+    pub fn with_store(mut self, store: Option<Arc<dyn BalanceStore>>) -> Self {
+        self.store = store;
+        self
+    }
 }
 
 impl BalanceApi {
     pub async fn send(&self, request: BalanceRequest) -> Result<BalanceResponse> {
-        tracing::info!(
-            "Making ANAF Balance API {version} call",
-            version = self.version
-        );
+        if let Some(response) = self.load(&request) {
+            tracing::debug!("Serving the balance from the store");
+            return Ok(BalanceResponse::from(response));
+        }
 
         let request_params = serde_qs::to_string(&request)?;
         let url = &format!("{}?{}", self.api_url, request_params);
 
-        tracing::debug!("URL: {:#?}", url);
-        tracing::debug!("Request: {:#?}", request);
+        tracing::trace!(target: PAYLOAD_TARGET, "Balance request: {:#?}", request);
 
-        let response = self.client.get(url).send().await?;
+        let response = self
+            .policy
+            .run(
+                ApiFamily::Balance,
+                &self.version,
+                HttpRequest::get(url),
+                None,
+                read_response::<BalanceRawResponse>,
+            )
+            .await?;
 
-        match response.status() {
-            StatusCode::SERVICE_UNAVAILABLE => Err(ApiError::ServiceUnavailable),
-            StatusCode::OK => {
-                let response = response.json::<BalanceRawResponse>().await?;
-                tracing::debug!("Response: {:#?}", response);
-                Ok(BalanceResponse::from(response))
+        if let Some(store) = &self.store {
+            if let Err(error) = store.put(&response) {
+                tracing::warn!("Failed to store the balance: {}", error);
             }
-            _ => {
-                let response = response.text().await?;
-                tracing::debug!("Error Response: {:#?}", response);
-                Err(ApiError::ApiError(response))
+        }
+
+        Ok(BalanceResponse::from(response))
+    }
+
+    fn load(&self, request: &BalanceRequest) -> Option<BalanceRawResponse> {
+        let store = self.store.as_ref()?;
+
+        match store.get(request.registration_code, request.year) {
+            Ok(response) => response,
+            Err(error) => {
+                tracing::warn!("Failed to load the stored balance: {}", error);
+                None
             }
         }
     }
+
+    /// Sends the request once and returns the response exactly as ANAF returned it.
+    ///
+    /// The store, the retry policy and the circuit breaker are skipped, but the rate limit applies.
+    pub async fn send_raw(&self, request: BalanceRequest) -> Result<RawResponse<BalanceResponse>> {
+        let url = format!("{}?{}", self.api_url, serde_qs::to_string(&request)?);
+
+        self.policy
+            .raw(
+                ApiFamily::Balance,
+                &self.version,
+                HttpRequest::get(&url),
+                None,
+            )
+            .await
+    }
 }
@@ -1,16 +1,18 @@
 use serde::Serialize;
 
+use crate::Cui;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct BalanceRequest {
     #[serde(rename = "cui")]
-    pub registration_code: usize,
+    pub registration_code: Cui,
 
     #[serde(rename = "an")]
     pub year: usize,
 }
 
 impl BalanceRequest {
-    pub fn new(registration_code: usize, year: usize) -> Self {
+    pub fn new(registration_code: Cui, year: usize) -> Self {
         Self {
             registration_code,
             year,
@@ -2,6 +2,8 @@ use std::{collections::BTreeMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{read_response, Cui, FromRawResponse, HttpResponse, Result};
+
 use super::Balance;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -10,7 +12,7 @@ pub struct BalanceRawResponse {
     pub year: usize,
 
     #[serde(alias = "cui")]
-    pub unique_registration_code: usize,
+    pub unique_registration_code: Cui,
 
     #[serde(alias = "deni")]
     pub name: String,
@@ -29,7 +31,7 @@ pub struct BalanceRawResponse {
 pub struct BalanceResponse {
     pub kind: EntityKind,
     pub year: usize,
-    pub unique_registration_code: usize,
+    pub unique_registration_code: Cui,
     pub name: String,
     pub activity_code: usize,
     pub activity_name: String,
@@ -71,6 +73,12 @@ impl From<BalanceRawResponse> for BalanceResponse {
     }
 }
 
+impl FromRawResponse for BalanceResponse {
+    fn from_raw(response: &HttpResponse) -> Result<Self> {
+        read_response::<BalanceRawResponse>(response).map(Self::from)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub enum EntityKind {
     Company,
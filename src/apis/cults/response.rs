@@ -1,47 +1,132 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
-use crate::ApiResponse;
+use crate::{date, nullable_date, text, ApiResponse, Cui, RegistryItem, ToAnafJson};
 
 pub type CultResponse = ApiResponse<CultResponseItem>;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CultResponseItem {
     #[serde(alias = "cui")]
-    pub unique_registration_code: usize,
+    pub unique_registration_code: Cui,
 
     #[serde(alias = "data")]
-    pub when: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub when: Option<NaiveDate>,
 
     #[serde(alias = "denumire")]
     pub name: String,
 
     #[serde(alias = "adresa")]
-    pub address: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub address: Option<String>,
 
     #[serde(alias = "nrRegCom")]
-    pub commerce_registry_number: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub commerce_registry_number: Option<String>,
 
     #[serde(alias = "telefon")]
-    pub phone: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub phone: Option<String>,
 
     #[serde(alias = "fax")]
-    pub fax: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub fax: Option<String>,
 
     #[serde(alias = "codPostal")]
-    pub postal_code: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub postal_code: Option<String>,
 
     #[serde(alias = "act")]
-    pub act: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub act: Option<String>,
 
     #[serde(alias = "stare_inregistrare")]
     pub registration_status: String,
 
     #[serde(alias = "dataInceputRegCult")]
-    pub cult_since: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub cult_since: Option<NaiveDate>,
 
     #[serde(alias = "dataAnulareRegCult")]
-    pub cult_until: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub cult_until: Option<NaiveDate>,
 
     #[serde(alias = "statusRegCult")]
     pub is_active: bool,
 }
+
+impl RegistryItem for CultResponseItem {
+    fn registration_code(&self) -> Cui {
+        self.unique_registration_code
+    }
+
+    fn query_date(&self) -> Option<NaiveDate> {
+        self.when
+    }
+}
+
+impl ToAnafJson for CultResponseItem {
+    fn to_anaf_json(&self) -> Value {
+        json!({
+            "cui": self.unique_registration_code,
+            "data": date(&self.when),
+            "denumire": self.name,
+            "adresa": text(&self.address),
+            "nrRegCom": text(&self.commerce_registry_number),
+            "telefon": text(&self.phone),
+            "fax": text(&self.fax),
+            "codPostal": text(&self.postal_code),
+            "act": text(&self.act),
+            "stare_inregistrare": self.registration_status,
+            "dataInceputRegCult": date(&self.cult_since),
+            "dataAnulareRegCult": nullable_date(&self.cult_until),
+            "statusRegCult": self.is_active,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use serde_json::Value;
+
+    use crate::{parse_body, ToAnafJson};
+
+    use super::CultResponse;
+
+    const RESPONSE: &str = r#"{
+        "cod": 200,
+        "message": "SUCCESS",
+        "found": [{
+            "cui": 49201783, "data": "2024-01-01", "denumire": "COMPANY SRL",
+            "adresa": "MUNICIPIUL BUCUREŞTI, SECTOR 1, STR. EXEMPLU, NR.1",
+            "nrRegCom": "J40/1/2020", "telefon": "", "fax": "", "codPostal": "010101", "act": "",
+            "stare_inregistrare": "INREGISTRAT din data 2020-01-01",
+            "dataInceputRegCult": "2020-01-01", "dataAnulareRegCult": null,
+            "statusRegCult": true
+        }, {
+            "cui": 40914732, "data": "2024-01-01", "denumire": "OTHER SRL", "adresa": "",
+            "nrRegCom": "", "telefon": "", "fax": "", "codPostal": "", "act": "",
+            "stare_inregistrare": "RADIAT", "dataInceputRegCult": "2019-01-01",
+            "dataAnulareRegCult": "2023-06-01", "statusRegCult": false
+        }],
+        "notFound": [111111115]
+    }"#;
+
+    #[test]
+    fn response_normalizes_placeholders() {
+        let response = parse_body::<CultResponse>(RESPONSE.as_bytes()).unwrap();
+
+        assert_eq!(response.data[0].fax, None);
+        assert_eq!(response.data[0].cult_until, None);
+        assert_eq!(
+            response.data[1].cult_until,
+            NaiveDate::from_ymd_opt(2023, 6, 1)
+        );
+
+        let expected = serde_json::from_str::<Value>(RESPONSE).unwrap();
+        assert_eq!(response.to_anaf_json(), expected);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    check_batch, check_body, record_found, send_in_batches, snippet, ApiError, ApiFamily,
+    ApiRequest, ApiResponse, BodyStatus, CallPolicy, CircuitBreaker, EndpointCache,
+    EndpointCoalescer, Fetch, HttpRequest, HttpResponse, RateLimiter, RawResponse, Result,
+    RetryPolicy, Transport, PAYLOAD_TARGET,
+};
+
+/// ANAF endpoint
+///
+/// Describes an ANAF registry endpoint which takes a batch of requests as a JSON body and
+/// answers with a JSON response. [`EndpointApi`] sends the requests of any endpoint.
+///
+/// Adding a new registry only requires declaring its types:
+///
+/// ```rust
+/// use anaf_api::{
+///     vat_payer::{VatPayerApiVersion, VatPayerResponse},
+///     AnafClient, AnafEndpoint, ApiFamily, ApiRequest, EndpointApi,
+/// };
+///
+/// #[derive(Debug, Clone)]
+/// pub struct VatPayerEndpoint;
+///
+/// impl AnafEndpoint for VatPayerEndpoint {
+///     const NAME: &'static str = "VatPayer";
+///     const FAMILY: ApiFamily = ApiFamily::VatPayer;
+///
+///     type Version = VatPayerApiVersion;
+///     type Request = ApiRequest;
+///     type Response = VatPayerResponse;
+///
+///     fn path(version: &Self::Version) -> String {
+///         format!("/PlatitorTvaRest/api/{}/ws/tva", version)
+///     }
+/// }
+///
+/// let api: EndpointApi<VatPayerEndpoint> = AnafClient::new().endpoint(VatPayerApiVersion::V8);
+/// ```
+pub trait AnafEndpoint {
+    /// Name used in logs.
+    const NAME: &'static str;
+
+    /// Family used for client-wide policies, such as rate limiting.
+    const FAMILY: ApiFamily;
+
+    type Version: Display + Debug + Clone + Send + Sync;
+    type Request: Serialize + Debug + Send + Sync;
+    type Response: DeserializeOwned + Debug + Send + BodyStatus;
+
+    /// Path of the endpoint, relative to the base URL of the ANAF web services.
+    fn path(version: &Self::Version) -> String;
+}
+
+/// Sends requests to an [`AnafEndpoint`].
+#[derive(Debug, Clone)]
+pub struct EndpointApi<E: AnafEndpoint> {
+    api_url: String,
+    version: E::Version,
+    policy: CallPolicy,
+    cache: Option<Arc<dyn EndpointCache<E>>>,
+    coalescer: Option<Arc<dyn EndpointCoalescer<E>>>,
+    endpoint: PhantomData<fn() -> E>,
+}
+
+impl<E: AnafEndpoint> EndpointApi<E> {
+    pub fn new(version: E::Version, transport: Arc<dyn Transport>, api_url: &str) -> Self {
+        Self {
+            version,
+            api_url: api_url.to_owned(),
+            policy: CallPolicy::new(transport),
+            cache: None,
+            coalescer: None,
+            endpoint: PhantomData,
+        }
+    }
+
+    /// Paces the requests of this handle with the given rate limiter.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<RateLimiter>) -> Self {
+        self.policy.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Fails fast while the given circuit breaker is open.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Option<CircuitBreaker>) -> Self {
+        self.policy.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// Retries failed requests of this handle with the given policy.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.policy.retry_policy = retry_policy;
+        self
+    }
+
+    /// Serves the companies found in the given cache without asking ANAF again.
+    pub fn with_cache(mut self, cache: Option<Arc<dyn EndpointCache<E>>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Shares the companies in flight with the other calls using the given coalescer.
+    pub fn with_coalescer(mut self, coalescer: Option<Arc<dyn EndpointCoalescer<E>>>) -> Self {
+        self.coalescer = coalescer;
+        self
+    }
+
+    pub fn version(&self) -> &E::Version {
+        &self.version
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+}
+
+impl<E: AnafEndpoint> EndpointApi<E> {
+    pub async fn send(&self, request: Vec<E::Request>) -> Result<E::Response> {
+        check_batch(E::FAMILY, &self.version, request.len())?;
+
+        let (request, cached) = match &self.cache {
+            Some(cache) => cache.lookup(&self.version, request),
+            None => (request, None),
+        };
+
+        if request.is_empty() {
+            if let Some(cached) = cached {
+                tracing::debug!("Serving the whole request from the cache");
+                return Ok(cached);
+            }
+        }
+
+        let response = match &self.coalescer {
+            Some(coalescer) => {
+                let fetch: Fetch<'_, E> = Box::new(|request| Box::pin(self.fetch(request)));
+                coalescer.send(&self.version, request, fetch).await?
+            }
+            None => self.fetch(request).await?,
+        };
+
+        match (&self.cache, cached) {
+            (Some(cache), Some(cached)) => Ok(cache.merge(cached, response)),
+            _ => Ok(response),
+        }
+    }
+
+    async fn fetch(&self, request: Vec<E::Request>) -> Result<E::Response> {
+        tracing::trace!(target: PAYLOAD_TARGET, "{} request: {:#?}", E::NAME, request);
+
+        let http_request = HttpRequest::post_json(&self.api_url, &request)?;
+        let response = self
+            .policy
+            .run(
+                E::FAMILY,
+                &self.version,
+                http_request,
+                Some(request.len()),
+                |response| {
+                    let response = read_response::<E::Response>(response).and_then(check_body)?;
+                    record_found(
+                        E::FAMILY,
+                        &self.version,
+                        response.found(),
+                        response.not_found().len(),
+                    );
+
+                    Ok(response)
+                },
+            )
+            .await?;
+
+        if let Some(cache) = &self.cache {
+            cache.store(&self.version, &request, &response);
+        }
+
+        Ok(response)
+    }
+
+    /// Sends the request once and returns the response exactly as ANAF returned it.
+    ///
+    /// The cache, the coalescing, the retry policy and the circuit breaker are skipped, but the
+    /// rate limit applies.
+    pub async fn send_raw(&self, request: Vec<E::Request>) -> Result<RawResponse<E::Response>> {
+        check_batch(E::FAMILY, &self.version, request.len())?;
+
+        let http_request = HttpRequest::post_json(&self.api_url, &request)?;
+
+        self.policy
+            .raw(E::FAMILY, &self.version, http_request, Some(request.len()))
+            .await
+    }
+}
+
+impl<E, T> EndpointApi<E>
+where
+    E: AnafEndpoint<Request = ApiRequest, Response = ApiResponse<T>>,
+{
+    /// Sends any number of companies, split into batches of at most [`crate::MAX_REQUEST_SIZE`].
+    ///
+    /// The batches are sent one after another and their responses are merged into one.
+    pub async fn send_all(&self, request: Vec<ApiRequest>) -> Result<E::Response> {
+        if request.is_empty() {
+            check_batch(E::FAMILY, &self.version, 0)?;
+        }
+
+        send_in_batches(request, |chunk| self.send(chunk)).await
+    }
+}
+
+/// Maps the HTTP status of an ANAF response and deserializes its body.
+pub(crate) fn read_response<T>(response: &HttpResponse) -> Result<T>
+where
+    T: DeserializeOwned + Debug,
+{
+    match response.status {
+        StatusCode::OK => {
+            let response = parse_body::<T>(&response.body)?;
+            tracing::trace!(target: PAYLOAD_TARGET, "Response: {:#?}", response);
+            Ok(response)
+        }
+        _ => {
+            tracing::trace!(target: PAYLOAD_TARGET, "Error response: {}", response.text());
+            Err(ApiError::from_response(response))
+        }
+    }
+}
+
+/// Deserializes an ANAF response body, reporting where it does not match the expected shape.
+pub(crate) fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(body);
+
+    serde_path_to_error::deserialize(deserializer).map_err(|error| ApiError::MalformedResponse {
+        path: error.path().to_string(),
+        message: error.inner().to_string(),
+        body: snippet(body),
+    })
+}
@@ -1,3 +1,7 @@
+mod endpoint;
+
+pub use endpoint::*;
+
 #[cfg(feature = "balance_api")]
 pub mod balance;
 #[cfg(feature = "cults_api")]
@@ -1,92 +1,60 @@
-use reqwest::{Client, StatusCode};
-
-use crate::{ApiError, ApiRequest, Result};
+use crate::{AnafEndpoint, ApiFamily, ApiRequest, EndpointApi};
 
 use super::{VatPayerApiVersion, VatPayerResponse};
 
-#[derive(Debug)]
-pub struct VatPayerApi {
-    api_url: String,
-    version: VatPayerApiVersion,
-    client: Client,
-}
+#[derive(Debug, Clone)]
+pub struct VatPayerEndpoint;
 
-impl VatPayerApi {
-    pub fn new(version: VatPayerApiVersion, client: Client, api_url: &str) -> Self {
-        Self {
-            version,
-            client,
-            api_url: api_url.to_owned(),
-        }
-    }
-}
+impl AnafEndpoint for VatPayerEndpoint {
+    const NAME: &'static str = "VatPayer";
+    const FAMILY: ApiFamily = ApiFamily::VatPayer;
 
-impl VatPayerApi {
-    pub async fn send(&self, request: Vec<ApiRequest>) -> Result<VatPayerResponse> {
-        if request.is_empty() || request.len() >= 500 {
-            return Err(ApiError::InvalidRequestError(request.len()));
-        }
+    type Version = VatPayerApiVersion;
+    type Request = ApiRequest;
+    type Response = VatPayerResponse;
 
-        tracing::info!(
-            "Making ANAF VatPayer API {version} call",
-            version = self.version
-        );
-        tracing::debug!("URL: {:#?}", self.api_url);
-        tracing::debug!("Request: {:#?}", request);
-
-        let response = self
-            .client
-            .post(&self.api_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        match response.status() {
-            StatusCode::SERVICE_UNAVAILABLE => Err(ApiError::ServiceUnavailable),
-            StatusCode::OK => {
-                let response = response.json::<VatPayerResponse>().await?;
-                tracing::debug!("Response: {:#?}", response);
-                Ok(response)
-            }
-            _ => {
-                let response = response.text().await?;
-                tracing::debug!("Error Response: {:#?}", response);
-                Err(ApiError::ApiError(response))
-            }
-        }
+    fn path(version: &Self::Version) -> String {
+        format!("/PlatitorTvaRest/api/{}/ws/tva", version)
     }
 }
 
+pub type VatPayerApi = EndpointApi<VatPayerEndpoint>;
+
 #[cfg(test)]
 mod test {
+    use std::time::{Duration, Instant};
+
     use chrono::Utc;
-    use reqwest::Client;
 
     use crate::{
-        vat_payer::{VatPayerApi, VatPayerApiVersion},
-        ApiRequest,
+        vat_payer::VatPayerApiVersion, AnafClient, AnafErrorKind, ApiError, ApiRequest, Cui,
+        RateLimit, RetryPolicy,
     };
 
     #[tokio::test]
     async fn api_handles_200() {
         let mut server = mockito::Server::new_async().await;
 
-        let mock_url = server.url();
-
         let version = VatPayerApiVersion::V8;
         let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
-        let api_url = format!("{}{}", mock_url, endpoint);
 
         let mock = server
-            .mock("POST", endpoint.clone().as_str())
+            .mock("POST", endpoint.as_str())
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body("{\"cod\":200,\"message\":\"\",\"data\":[],\"not_found\":[]}")
             .create();
 
-        let request = vec![ApiRequest::new(111111111, Utc::now().date_naive())];
-        let api = VatPayerApi::new(version, Client::new(), api_url.clone().as_str());
-        let response = api.send(request).await;
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request).await;
 
         assert!(response.is_ok());
         mock.assert_async().await;
@@ -96,23 +64,394 @@ mod test {
     async fn api_handles_503() {
         let mut server = mockito::Server::new_async().await;
 
-        let mock_url = server.url();
-
         let version = VatPayerApiVersion::V8;
         let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
-        let api_url = format!("{}{}", mock_url, endpoint);
 
         let mock = server
-            .mock("POST", endpoint.clone().as_str())
+            .mock("POST", endpoint.as_str())
             .with_status(503)
             .with_header("content-type", "text/html")
             .create();
 
-        let request = vec![ApiRequest::new(111111111, Utc::now().date_naive())];
-        let api = VatPayerApi::new(version, Client::new(), api_url.clone().as_str());
-        let response = api.send(request).await;
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request).await;
 
         assert!(response.is_err());
         mock.assert_async().await;
     }
+
+    #[tokio::test]
+    async fn api_reports_malformed_response() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"found\":[],\"notFound\":[\"abc\"]}")
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request).await;
+
+        match response {
+            Err(ApiError::MalformedResponse { path, body, .. }) => {
+                assert_eq!(path, "notFound[0]");
+                assert!(body.contains("abc"));
+            }
+            response => panic!("unexpected response: {response:?}"),
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_rejects_error_codes() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                "{\"cod\":400,\"message\":\"CUI invalid\",\"found\":[],\"notFound\":[111111115]}",
+            )
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request).await;
+
+        match response {
+            Err(ApiError::Refused {
+                code,
+                kind,
+                not_found,
+                ..
+            }) => {
+                assert_eq!(code, 400);
+                assert_eq!(kind, AnafErrorKind::InvalidRegistrationCode);
+                assert_eq!(not_found, vec![111111115]);
+            }
+            response => panic!("unexpected response: {response:?}"),
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_returns_raw_response() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("x-anaf-test", "raw")
+            .with_body("{\"cod\":200,\"message\":\"\",\"found\":[],\"notFound\":[111111115]}")
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send_raw(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-anaf-test"], "raw");
+        assert!(response.url.ends_with(&endpoint));
+        assert_eq!(response.parse().unwrap().not_found, vec![111111115]);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_uses_custom_user_agent() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .match_header("user-agent", "anaf-api-test")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"data\":[],\"not_found\":[]}")
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .user_agent("anaf-api-test")
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request).await;
+
+        assert!(response.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_is_shared_across_tasks() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"data\":[],\"not_found\":[]}")
+            .expect(4)
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .rate_limit(RateLimit::per_second(2))
+            .build()
+            .unwrap();
+
+        let started_at = Instant::now();
+
+        // distinct companies, so that the calls are not coalesced
+        let handles = (1..=4)
+            .map(|base| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let request = vec![ApiRequest::new(
+                        Cui::from_base(base),
+                        Utc::now().date_naive(),
+                    )];
+                    client.vat_payer(Default::default()).send(request).await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+
+        // the clones share one rate limiter, so the calls are paced 500ms apart
+        assert!(started_at.elapsed() >= Duration::from_millis(1500));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_coalesces_same_company_across_tasks() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"found\":[],\"notFound\":[111111115]}")
+            .expect(1)
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .rate_limit(RateLimit::per_second(2))
+            .build()
+            .unwrap();
+
+        let when = Utc::now().date_naive();
+        let handles = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let request = vec![ApiRequest::new(Cui::new(111111115).unwrap(), when)];
+                    client.vat_payer(Default::default()).send(request).await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap().not_found, vec![111111115]);
+        }
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_accepts_exactly_500_companies() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"data\":[],\"not_found\":[]}")
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let now = Utc::now().date_naive();
+        let request = (1..=500)
+            .map(|it| ApiRequest::new(Cui::from_base(it), now))
+            .collect();
+        let response = client.vat_payer(version).send(request).await;
+
+        assert!(response.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_sends_all_in_batches() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"found\":[],\"notFound\":[1]}")
+            .expect(3)
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let now = Utc::now().date_naive();
+        let request = (1..=1001)
+            .map(|it| ApiRequest::new(Cui::from_base(it), now))
+            .collect();
+        let response = client.vat_payer(version).send_all(request).await.unwrap();
+
+        assert_eq!(response.not_found, vec![1, 1, 1]);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_reports_failed_batch() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(503)
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let now = Utc::now().date_naive();
+        let request = (1..=3)
+            .map(|it| ApiRequest::new(Cui::from_base(it), now))
+            .collect();
+        let response = client.vat_payer(version).send_all(request).await;
+
+        match response {
+            Err(ApiError::BatchError {
+                chunk,
+                registration_codes,
+                source,
+            }) => {
+                assert_eq!(chunk, 0);
+                assert_eq!(
+                    registration_codes,
+                    (1..=3).map(Cui::from_base).collect::<Vec<_>>()
+                );
+                assert!(matches!(*source, ApiError::ServiceUnavailable { .. }));
+            }
+            _ => panic!("expected a batch error"),
+        }
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_retries_503() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let unavailable = server
+            .mock("POST", endpoint.as_str())
+            .with_status(503)
+            .with_header("retry-after", "0")
+            .expect(2)
+            .create();
+
+        let available = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"data\":[],\"not_found\":[]}")
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .without_rate_limit()
+            .retry_policy(RetryPolicy::default())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request).await;
+
+        assert!(response.is_ok());
+        unavailable.assert_async().await;
+        available.assert_async().await;
+    }
 }
@@ -1,78 +1,486 @@
-use reqwest::{Client, StatusCode};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::{ApiError, ApiRequest, Result};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    check_batch, check_body, normalize, parse_body, read_response, record_found, ApiError,
+    ApiFamily, ApiRequest, BodyStatus, CallPolicy, CircuitBreaker, HttpRequest, HttpResponse,
+    RateLimiter, RawResponse, Result, RetryPolicy, Transport, PAYLOAD_TARGET,
+};
 
 use super::{VatPayerApiVersion, VatPayerAsyncResponse, VatPayerAsyncToken, VatPayerResponse};
 
-#[derive(Debug)]
+/// ANAF requires waiting at least 2 seconds after submitting a request before the first poll.
+pub const MIN_ASYNC_INITIAL_DELAY: Duration = Duration::from_secs(2);
+
+/// ANAF requires waiting at least 10 seconds between two consecutive polls.
+pub const MIN_ASYNC_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long [`VatPayerAsyncApi::fetch`] keeps polling before giving up, unless configured otherwise.
+pub const DEFAULT_ASYNC_DEADLINE: Duration = Duration::from_secs(5 * 60);
+
+/// VatPayer Async API
+///
+/// Submits a batch of companies and polls for the result, following ANAF's timing rules.
+///
+/// # Example
+/// ```rust,no_run
+/// # use anaf_api::{vat_payer::VatPayerApiVersion, AnafClient, ApiRequest};
+/// # async fn run() -> anaf_api::Result<()> {
+/// let client = AnafClient::new();
+/// let now = chrono::Local::now().date_naive();
+///
+/// let request = vec![ApiRequest::new("RO49201783".parse()?, now)];
+/// let response = client
+///     .async_vat_payer(VatPayerApiVersion::V8)
+///     .send_and_wait(request)
+///     .await?;
+///
+/// dbg!(&response);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
 pub struct VatPayerAsyncApi {
     api_url: String,
     version: VatPayerApiVersion,
-    client: Client,
+    policy: CallPolicy,
+    initial_delay: Duration,
+    poll_interval: Duration,
+    deadline: Duration,
 }
 
 impl VatPayerAsyncApi {
-    pub fn new(version: VatPayerApiVersion, client: Client, api_url: &str) -> Self {
+    pub fn new(version: VatPayerApiVersion, transport: Arc<dyn Transport>, api_url: &str) -> Self {
         Self {
             version,
-            client,
             api_url: api_url.to_owned(),
+            policy: CallPolicy::new(transport),
+            initial_delay: MIN_ASYNC_INITIAL_DELAY,
+            poll_interval: MIN_ASYNC_POLL_INTERVAL,
+            deadline: DEFAULT_ASYNC_DEADLINE,
         }
     }
+
+    /// Paces the requests of this handle with the given rate limiter.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<RateLimiter>) -> Self {
+        self.policy.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Fails fast while the given circuit breaker is open.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: Option<CircuitBreaker>) -> Self {
+        self.policy.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// Retries failed requests of this handle with the given policy.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.policy.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the delay before the first poll. Values below [`MIN_ASYNC_INITIAL_DELAY`] are raised to it.
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay.max(MIN_ASYNC_INITIAL_DELAY);
+        self
+    }
+
+    /// Sets the delay between polls. Values below [`MIN_ASYNC_POLL_INTERVAL`] are raised to it.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval.max(MIN_ASYNC_POLL_INTERVAL);
+        self
+    }
+
+    /// Sets the overall time [`VatPayerAsyncApi::fetch`] waits for the result.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
 }
 
 impl VatPayerAsyncApi {
+    /// Submits the request and returns the token used to fetch the result.
     pub async fn send(&self, request: Vec<ApiRequest>) -> Result<VatPayerAsyncResponse> {
-        if request.is_empty() || request.len() >= 500 {
-            return Err(ApiError::InvalidRequestError(request.len()));
-        }
+        check_batch(ApiFamily::VatPayerAsync, &self.version, request.len())?;
 
-        tracing::info!(
-            "Making ANAF VatPayer Async API {version} call",
-            version = self.version
-        );
-        tracing::debug!("URL: {:#?}", self.api_url);
-        tracing::debug!("Request: {:#?}", request);
-
-        let response = self
-            .client
-            .post(&self.api_url)
-            .json(&request)
-            .send()
-            .await?;
-
-        match response.status() {
-            StatusCode::SERVICE_UNAVAILABLE => Err(ApiError::ServiceUnavailable),
-            StatusCode::OK => {
-                let response = response.json::<VatPayerAsyncResponse>().await?;
-                tracing::debug!("Response: {:#?}", response);
-                Ok(response)
+        tracing::trace!(target: PAYLOAD_TARGET, "VatPayer Async request: {:#?}", request);
+
+        let http_request = HttpRequest::post_json(&self.api_url, &request)?;
+
+        self.policy
+            .run(
+                ApiFamily::VatPayerAsync,
+                &self.version,
+                http_request,
+                Some(request.len()),
+                |response| read_response(response).and_then(check_body),
+            )
+            .await
+    }
+
+    /// Submits the request once and returns the response exactly as ANAF returned it.
+    pub async fn send_raw(
+        &self,
+        request: Vec<ApiRequest>,
+    ) -> Result<RawResponse<VatPayerAsyncResponse>> {
+        check_batch(ApiFamily::VatPayerAsync, &self.version, request.len())?;
+
+        let http_request = HttpRequest::post_json(&self.api_url, &request)?;
+
+        self.policy
+            .raw(
+                ApiFamily::VatPayerAsync,
+                &self.version,
+                http_request,
+                Some(request.len()),
+            )
+            .await
+    }
+
+    /// Polls once for the result of a submitted request and returns the response exactly as
+    /// ANAF returned it. It only parses once the result is ready.
+    pub async fn fetch_raw(
+        &self,
+        token: &VatPayerAsyncToken,
+    ) -> Result<RawResponse<VatPayerResponse>> {
+        let request = HttpRequest::get(&format!("{}?id={}", self.api_url, token));
+
+        self.policy
+            .raw(ApiFamily::VatPayerAsync, &self.version, request, None)
+            .await
+    }
+
+    /// Polls once for the result of a submitted request.
+    ///
+    /// Returns `None` while ANAF is still processing the request. Failed polls are retried no
+    /// sooner than the poll interval.
+    pub async fn try_fetch(&self, token: &VatPayerAsyncToken) -> Result<Option<VatPayerResponse>> {
+        let request = HttpRequest::get(&format!("{}?id={}", self.api_url, token));
+
+        // a retried poll is still a poll, so it must wait as long as one
+        let mut policy = self.policy.clone();
+        policy.retry_policy = policy.retry_policy.with_min_backoff(self.poll_interval);
+
+        policy
+            .run(
+                ApiFamily::VatPayerAsync,
+                &self.version,
+                request,
+                None,
+                |response| {
+                    let response = read_result(response)?;
+                    if let Some(response) = &response {
+                        record_found(
+                            ApiFamily::VatPayerAsync,
+                            &self.version,
+                            response.data.len(),
+                            response.not_found.len(),
+                        );
+                    }
+
+                    Ok(response)
+                },
+            )
+            .await
+    }
+
+    /// Waits for the result of a submitted request.
+    ///
+    /// The first poll happens after the initial delay, and the following ones after each poll
+    /// interval, until the result is ready or the deadline is exceeded.
+    pub async fn fetch(&self, token: &VatPayerAsyncToken) -> Result<VatPayerResponse> {
+        let started_at = Instant::now();
+        let mut delay = self.initial_delay;
+
+        loop {
+            if started_at.elapsed() + delay > self.deadline {
+                return Err(ApiError::AsyncDeadlineExceeded(token.to_string()));
             }
-            _ => {
-                let response = response.text().await?;
-                tracing::debug!("Error Response: {:#?}", response);
-                Err(ApiError::ApiError(response))
+
+            tokio::time::sleep(delay).await;
+
+            if let Some(response) = self.try_fetch(token).await? {
+                return Ok(response);
             }
+
+            tracing::debug!("Result for {} is not ready yet", token);
+            delay = self.poll_interval;
         }
     }
 
-    // TODO: finish the implementation
-    pub async fn fetch(&self, token: VatPayerAsyncToken) -> anyhow::Result<VatPayerResponse> {
-        // TODO: implement retry logic: min 2 seconds, min 10 seconds between retries
+    /// Submits the request and waits for its result.
+    pub async fn send_and_wait(&self, request: Vec<ApiRequest>) -> Result<VatPayerResponse> {
+        let response = self.send(request).await?;
+
+        self.fetch(&response.token).await
+    }
+}
+
+/// Body of a poll whose result is not ready, holding only ANAF's status.
+#[derive(Debug, Deserialize)]
+struct PollStatus {
+    #[serde(alias = "cod")]
+    status: usize,
+
+    #[serde(default)]
+    message: String,
+}
+
+impl BodyStatus for PollStatus {
+    fn code(&self) -> usize {
+        self.status
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Reads a poll response, which is `None` while ANAF is still processing the request.
+///
+/// Any other status, such as an unknown or expired token, is an error.
+fn read_result(response: &HttpResponse) -> Result<Option<VatPayerResponse>> {
+    if response.status != StatusCode::OK {
+        tracing::trace!(target: PAYLOAD_TARGET, "Error response: {}", response.text());
+        return Err(ApiError::from_response(response));
+    }
 
-        let url = format!("{}?id={}", self.api_url, token);
-        tracing::info!(
-            "Making ANAF VatPayer Async API {version} call",
-            version = self.version
+    let body = parse_body::<serde_json::Value>(&response.body)?;
+    tracing::trace!(target: PAYLOAD_TARGET, "Response: {:#?}", body);
+
+    // until the result is ready, ANAF answers with a status message only
+    if body.get("found").is_none() && body.get("data").is_none() {
+        let status = parse_body::<PollStatus>(&response.body).and_then(check_body)?;
+
+        if is_processing(&status.message) {
+            return Ok(None);
+        }
+    }
+
+    parse_body::<VatPayerResponse>(&response.body)
+        .and_then(check_body)
+        .map(Some)
+}
+
+/// Recognizes the message ANAF sends while the request is being processed, e.g. "Cererea nu a
+/// fost procesata inca".
+fn is_processing(message: &str) -> bool {
+    normalize(message).contains("proces")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use chrono::Utc;
+    use reqwest::{Method, StatusCode};
+    use tokio::time::Instant;
+
+    use crate::{
+        vat_payer::VatPayerApiVersion, AnafClient, ApiError, ApiRequest, Cui, HttpRequest,
+        HttpResponse, InMemoryTransport, RetryPolicy, Transport, TransportFuture,
+    };
+
+    use super::{MIN_ASYNC_INITIAL_DELAY, MIN_ASYNC_POLL_INTERVAL};
+
+    const PATH: &str = "/AsynchWebService/api/v8/ws/tva";
+
+    /// Records when each request was sent.
+    #[derive(Debug, Clone, Default)]
+    struct TimedTransport {
+        inner: InMemoryTransport,
+        sent_at: Arc<Mutex<Vec<Instant>>>,
+    }
+
+    impl Transport for TimedTransport {
+        fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+            self.sent_at.lock().unwrap().push(Instant::now());
+            self.inner.send(request)
+        }
+    }
+
+    #[tokio::test]
+    async fn api_submits_and_polls() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/AsynchWebService/api/{}/ws/tva", version);
+
+        let submit = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"SUCCESS\",\"correlationId\":\"abc-123\"}")
+            .create();
+
+        let poll = server
+            .mock("GET", endpoint.as_str())
+            .match_query(mockito::Matcher::UrlEncoded("id".into(), "abc-123".into()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                "{\"cod\":200,\"message\":\"SUCCESS\",\"found\":[],\"notFound\":[111111115]}",
+            )
+            .create();
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client
+            .async_vat_payer(version)
+            .send_and_wait(request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.not_found, vec![111111115]);
+        submit.assert_async().await;
+        poll.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn api_respects_deadline() {
+        let mut server = mockito::Server::new_async().await;
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/AsynchWebService/api/{}/ws/tva", version);
+
+        let client = AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let poll = server.mock("GET", endpoint.as_str()).expect(0).create();
+
+        let token = serde_json::from_str("\"abc-123\"").unwrap();
+        let response = client
+            .async_vat_payer(version)
+            .with_deadline(Duration::from_secs(1))
+            .fetch(&token)
+            .await;
+
+        assert!(matches!(response, Err(ApiError::AsyncDeadlineExceeded(_))));
+        poll.assert_async().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn api_spaces_retried_polls() {
+        let transport = TimedTransport::default();
+        transport.inner.respond(
+            Method::GET,
+            PATH,
+            HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+        );
+        transport.inner.respond(
+            Method::GET,
+            PATH,
+            HttpResponse::json(r#"{"cod":200,"message":"SUCCESS","found":[],"notFound":[19]}"#),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .retry_policy(RetryPolicy::default())
+            .build()
+            .unwrap();
+
+        let token = serde_json::from_str("\"abc-123\"").unwrap();
+        let response = client
+            .async_vat_payer(VatPayerApiVersion::V8)
+            .try_fetch(&token)
+            .await
+            .unwrap();
+        assert!(response.is_some());
+
+        let sent_at = transport.sent_at.lock().unwrap();
+        assert_eq!(sent_at.len(), 2);
+        assert!(sent_at[1] - sent_at[0] >= MIN_ASYNC_POLL_INTERVAL);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn api_polls_until_ready() {
+        let transport = TimedTransport::default();
+        transport.inner.respond(
+            Method::POST,
+            PATH,
+            HttpResponse::json(r#"{"cod":200,"message":"SUCCESS","correlationId":"abc-123"}"#),
+        );
+        for _ in 0..2 {
+            transport.inner.respond(
+                Method::GET,
+                PATH,
+                HttpResponse::json(r#"{"cod":200,"message":"Cererea nu a fost procesata inca"}"#),
+            );
+        }
+        transport.inner.respond(
+            Method::GET,
+            PATH,
+            HttpResponse::json(r#"{"cod":200,"message":"SUCCESS","found":[],"notFound":[19]}"#),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .build()
+            .unwrap();
+
+        let started_at = Instant::now();
+        let response = client
+            .async_vat_payer(VatPayerApiVersion::V8)
+            .send_and_wait(vec![ApiRequest::new(
+                Cui::from_base(1),
+                Utc::now().date_naive(),
+            )])
+            .await
+            .unwrap();
+        assert_eq!(response.not_found, vec![19]);
+
+        let sent_at = transport.sent_at.lock().unwrap();
+        assert_eq!(sent_at.len(), 4);
+        assert!(sent_at[1] - started_at >= MIN_ASYNC_INITIAL_DELAY);
+        assert!(sent_at[2] - sent_at[1] >= MIN_ASYNC_POLL_INTERVAL);
+        assert!(sent_at[3] - sent_at[2] >= MIN_ASYNC_POLL_INTERVAL);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn api_fails_on_refused_polls() {
+        let transport = InMemoryTransport::new();
+        transport.respond(
+            Method::GET,
+            PATH,
+            HttpResponse::json(r#"{"cod":500,"message":"Eroare interna"}"#),
+        );
+        transport.respond(
+            Method::GET,
+            PATH,
+            HttpResponse::new(StatusCode::NOT_FOUND, ""),
         );
-        tracing::debug!("URL: {:#?}", self.api_url);
-        tracing::debug!("Token: {:#?}", token);
 
-        let response = self.client.get(&url).send().await?;
-        let response = response.json::<VatPayerResponse>().await?;
+        let client = AnafClient::builder()
+            .transport(transport)
+            .without_rate_limit()
+            .build()
+            .unwrap();
+        let api = client.async_vat_payer(VatPayerApiVersion::V8);
+        let token = serde_json::from_str("\"abc-123\"").unwrap();
 
-        tracing::debug!("Response: {:#?}", response);
+        let response = api.fetch(&token).await;
+        assert!(matches!(response, Err(ApiError::Refused { code: 500, .. })));
 
-        Ok(response)
+        let response = api.fetch(&token).await;
+        assert!(matches!(response, Err(ApiError::NotFound { .. })));
     }
 }
@@ -3,10 +3,12 @@ mod api;
 #[cfg(feature = "vat_payer_async_api")]
 mod api_async;
 mod response;
+mod timeline;
 mod version;
 
 pub use api::*;
 #[cfg(feature = "vat_payer_async_api")]
 pub use api_async::*;
 pub use response::*;
+pub use timeline::*;
 pub use version::*;
@@ -1,14 +1,16 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 
-use crate::ApiResponse;
+use crate::{date, text, ApiResponse, Cui, RegistryItem, ToAnafJson};
 
 #[cfg(feature = "vat_payer_async_api")]
 pub type VatPayerAsyncResponse = crate::AsyncApiResponse<VatPayerAsyncToken>;
 
 #[cfg(feature = "vat_payer_async_api")]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct VatPayerAsyncToken {
-    #[serde(alias = "correlationId")]
     pub id: String,
 }
 
@@ -21,7 +23,7 @@ impl std::fmt::Display for crate::vat_payer::VatPayerAsyncToken {
 
 pub type VatPayerResponse = ApiResponse<VatPayerResponseItem>;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct VatPayerResponseItem {
     #[serde(alias = "date_generale")]
     pub company_info: CompanyInfo,
@@ -45,163 +47,615 @@ pub struct VatPayerResponseItem {
     pub fiscal_address: Address,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl RegistryItem for VatPayerResponseItem {
+    fn registration_code(&self) -> Cui {
+        self.company_info.unique_registration_code
+    }
+
+    fn query_date(&self) -> Option<NaiveDate> {
+        self.company_info.when
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CompanyInfo {
     #[serde(alias = "cui")]
-    pub unique_registration_code: usize,
+    pub unique_registration_code: Cui,
 
     #[serde(alias = "data")]
-    pub when: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub when: Option<NaiveDate>,
 
     #[serde(alias = "denumire")]
     pub name: String,
 
     #[serde(alias = "adresa")]
-    pub address: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub address: Option<String>,
 
     #[serde(alias = "nrRegCom")]
-    pub commerce_registry_number: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub commerce_registry_number: Option<String>,
 
     #[serde(alias = "telefon")]
-    pub phone: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub phone: Option<String>,
 
     #[serde(alias = "fax")]
-    pub fax: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub fax: Option<String>,
 
     #[serde(alias = "codPostal")]
-    pub postal_code: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub postal_code: Option<String>,
 
     #[serde(alias = "act")]
-    pub act: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub act: Option<String>,
 
     #[serde(alias = "stare_inregistrare")]
     pub registration_status: String,
 
     #[serde(alias = "data_inregistrare")]
-    pub registration_date: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub registration_date: Option<NaiveDate>,
 
     #[serde(alias = "cod_CAEN")]
-    pub activity_code: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub activity_code: Option<String>,
 
     #[serde(alias = "iban")]
-    pub iban: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub iban: Option<String>,
 
     #[serde(alias = "statusRO_e_Factura")]
     pub has_ro_einvoice: bool,
 
     // added in v8
     #[serde(alias = "organFiscalCompetent")]
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
     pub trusted_fiscal_activity: Option<String>,
 
     // added in v8
     #[serde(alias = "forma_de_proprietate")]
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
     pub property_form: Option<String>,
 
     // added in v8
     #[serde(alias = "forma_organizare")]
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
     pub organization_form: Option<String>,
 
     // added in v8
-    #[serde(alias = "forma_juriidica")]
+    #[serde(alias = "forma_juridica", alias = "forma_juriidica")]
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
     pub juridic_form: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// VAT registration of the company.
+///
+/// v8 sends every registration period in `perioade_TVA`, while v7 only sends the latest one,
+/// inline. Both end up in `periods`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(from = "RawVatScope")]
 pub struct VatScope {
-    #[serde(alias = "scpTVA")]
+    /// Whether the company is a VAT payer on the date of the request.
     pub is_payer: bool,
 
+    pub periods: Vec<VatPayerInterval>,
+
+    /// Shape ANAF sent the scope in, which [`ToAnafJson`] writes back.
+    #[serde(skip)]
+    pub shape: VatScopeShape,
+}
+
+/// Shape of a VAT payer response, which changed between API versions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VatScopeShape {
+    /// Every period in `perioade_TVA`, sent by v8.
+    #[default]
+    Periods,
+
+    /// The latest period inline, sent by v7, which also lacks the fiscal authority and the
+    /// property, organization and juridic forms.
+    Inline,
+}
+
+#[derive(Deserialize)]
+struct RawVatScope {
+    #[serde(alias = "scpTVA")]
+    is_payer: bool,
+
     // added in v8
-    #[serde(alias = "perioade_TVA", flatten)]
-    pub payer_interval: VatPayerInterval,
+    #[serde(default, alias = "perioade_TVA")]
+    periods: Option<Vec<VatPayerInterval>>,
+
+    // v7
+    #[serde(flatten)]
+    interval: VatPayerInterval,
+}
+
+impl From<RawVatScope> for VatScope {
+    fn from(raw: RawVatScope) -> Self {
+        let (periods, shape) = match raw.periods {
+            Some(periods) => (periods, VatScopeShape::Periods),
+            None if raw.interval.is_empty() => (vec![], VatScopeShape::Inline),
+            None => (vec![raw.interval], VatScopeShape::Inline),
+        };
+
+        Self {
+            is_payer: raw.is_payer,
+            periods,
+            shape,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// VAT registration period.
+///
+/// >>**Note**: `to` is the day the registration was cancelled, from which on the company is no longer a VAT payer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct VatPayerInterval {
     #[serde(alias = "data_inceput_ScpTVA")]
-    pub from: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub from: Option<NaiveDate>,
 
     #[serde(alias = "data_sfarsit_ScpTVA")]
-    pub to: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub to: Option<NaiveDate>,
 
+    /// When ANAF operated the cancellation.
     #[serde(alias = "data_anul_imp_ScpTVA")]
-    pub cancelled_at: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub cancelled_at: Option<NaiveDate>,
 
     #[serde(alias = "mesaj_ScpTVA")]
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
     pub cancelled_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl VatPayerInterval {
+    /// Whether the company was a VAT payer on the given date, during this period.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_some_and(|from| from <= date) && self.to.is_none_or(|to| date < to)
+    }
+
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Time during which a company was not registered for VAT, between two registration periods
+/// or after the last one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VatPayerGap {
+    pub from: NaiveDate,
+    /// Start of the next registration, or `None` if the company has not registered again.
+    pub to: Option<NaiveDate>,
+}
+
+impl VatPayerResponseItem {
+    /// Whether the company was registered for VAT on the given date, according to its
+    /// registration periods.
+    pub fn was_vat_payer_on(&self, date: NaiveDate) -> bool {
+        self.vat_scope
+            .periods
+            .iter()
+            .any(|period| period.contains(date))
+    }
+
+    /// Lists the times the company was not registered for VAT, since its first registration.
+    pub fn vat_payer_gaps(&self) -> Vec<VatPayerGap> {
+        let mut periods = self
+            .vat_scope
+            .periods
+            .iter()
+            .filter_map(|period| Some((period.from?, period.to)))
+            .collect::<Vec<_>>();
+        periods.sort();
+
+        let mut gaps = vec![];
+        let mut covered_until = match periods.first() {
+            Some((from, _)) => Some(*from),
+            None => return gaps,
+        };
+
+        for (from, to) in periods {
+            let Some(until) = covered_until else {
+                break;
+            };
+
+            if until < from {
+                gaps.push(VatPayerGap {
+                    from: until,
+                    to: Some(from),
+                });
+            }
+
+            covered_until = to.map(|to| to.max(until));
+        }
+
+        if let Some(until) = covered_until {
+            gaps.push(VatPayerGap {
+                from: until,
+                to: None,
+            });
+        }
+
+        gaps
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct VatPayerIncome {
     #[serde(alias = "dataInceputTvaInc")]
-    pub from: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub from: Option<NaiveDate>,
     #[serde(alias = "dataSfarsitTvaInc")]
-    pub to: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub to: Option<NaiveDate>,
     #[serde(alias = "dataActualizareTvaInc")]
-    pub updated_at: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub updated_at: Option<NaiveDate>,
     #[serde(alias = "dataPublicareTvaInc")]
-    pub published_at: Option<String>,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub published_at: Option<NaiveDate>,
     #[serde(alias = "tipActTvaInc")]
     pub update_type: String,
     #[serde(alias = "statusTvaIncasare")]
     pub status: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct InactiveStatus {
     #[serde(alias = "dataInactivare")]
-    pub deactivated_at: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub deactivated_at: Option<NaiveDate>,
     #[serde(alias = "dataReactivare")]
-    pub reactivated_at: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub reactivated_at: Option<NaiveDate>,
     #[serde(alias = "dataPublicare")]
-    pub published_at: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub published_at: Option<NaiveDate>,
     #[serde(alias = "dataRadiere")]
-    pub erased_at: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub erased_at: Option<NaiveDate>,
     #[serde(alias = "statusInactivi")]
     pub status: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct VatSplit {
     #[serde(alias = "dataInceputSplitTVA")]
-    pub started_at: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub started_at: Option<NaiveDate>,
 
     #[serde(alias = "dataAnulareSplitTVA")]
-    pub cancelled_at: String,
+    #[serde(default, deserialize_with = "crate::deserialize_date")]
+    pub cancelled_at: Option<NaiveDate>,
 
     #[serde(alias = "statusSplitTVA")]
     pub status: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Address {
     #[serde(alias = "sdenumire_Strada", alias = "ddenumire_Strada")]
-    pub street: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub street: Option<String>,
 
     #[serde(alias = "snumar_Strada", alias = "dnumar_Strada")]
-    pub number: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub number: Option<String>,
 
     #[serde(alias = "sdenumire_Localitate", alias = "ddenumire_Localitate")]
-    pub town: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub town: Option<String>,
 
     #[serde(alias = "scod_Localitate", alias = "dcod_Localitate")]
-    pub town_code: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub town_code: Option<String>,
 
     #[serde(alias = "sdenumire_Judet", alias = "ddenumire_Judet")]
-    pub county: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub county: Option<String>,
 
     #[serde(alias = "scod_Judet", alias = "dcod_Judet")]
-    pub county_code: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub county_code: Option<String>,
 
     #[serde(alias = "scod_JudetAuto", alias = "dcod_JudetAuto")]
-    pub county_code_auto: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub county_code_auto: Option<String>,
 
     #[serde(alias = "stara", alias = "dtara")]
-    pub country: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub country: Option<String>,
 
     #[serde(alias = "sdetalii_Adresa", alias = "ddetalii_Adresa")]
-    pub details: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub details: Option<String>,
 
     #[serde(alias = "scod_Postal", alias = "dcod_Postal")]
-    pub postal_code: String,
+    #[serde(default, deserialize_with = "crate::deserialize_text")]
+    pub postal_code: Option<String>,
+}
+
+impl ToAnafJson for VatPayerResponseItem {
+    fn to_anaf_json(&self) -> Value {
+        let info = &self.company_info;
+        let income = &self.vat_payer_income;
+        let inactive = &self.inactive;
+        let split = &self.vat_split;
+
+        let mut general = json!({
+            "cui": info.unique_registration_code,
+            "data": date(&info.when),
+            "denumire": info.name,
+            "adresa": text(&info.address),
+            "nrRegCom": text(&info.commerce_registry_number),
+            "telefon": text(&info.phone),
+            "fax": text(&info.fax),
+            "codPostal": text(&info.postal_code),
+            "act": text(&info.act),
+            "stare_inregistrare": info.registration_status,
+            "data_inregistrare": date(&info.registration_date),
+            "cod_CAEN": text(&info.activity_code),
+            "iban": text(&info.iban),
+            "statusRO_e_Factura": info.has_ro_einvoice,
+        });
+        let mut scope = json!({ "scpTVA": self.vat_scope.is_payer });
+
+        match self.vat_scope.shape {
+            VatScopeShape::Periods => {
+                general["organFiscalCompetent"] = json!(text(&info.trusted_fiscal_activity));
+                general["forma_de_proprietate"] = json!(text(&info.property_form));
+                general["forma_organizare"] = json!(text(&info.organization_form));
+                general["forma_juridica"] = json!(text(&info.juridic_form));
+
+                scope["perioade_TVA"] = self.vat_scope.periods.iter().map(period).collect();
+            }
+            VatScopeShape::Inline => {
+                let latest = self.vat_scope.periods.first().cloned().unwrap_or_default();
+                for (name, value) in period(&latest) {
+                    scope[name] = value;
+                }
+            }
+        }
+
+        json!({
+            "date_generale": general,
+            "inregistrare_scop_Tva": scope,
+            "inregistrare_RTVAI": {
+                "dataInceputTvaInc": date(&income.from),
+                "dataSfarsitTvaInc": date(&income.to),
+                "dataActualizareTvaInc": date(&income.updated_at),
+                "dataPublicareTvaInc": date(&income.published_at),
+                "tipActTvaInc": income.update_type,
+                "statusTvaIncasare": income.status,
+            },
+            "stare_inactiv": {
+                "dataInactivare": date(&inactive.deactivated_at),
+                "dataReactivare": date(&inactive.reactivated_at),
+                "dataPublicare": date(&inactive.published_at),
+                "dataRadiere": date(&inactive.erased_at),
+                "statusInactivi": inactive.status,
+            },
+            "inregistrare_SplitTVA": {
+                "dataInceputSplitTVA": date(&split.started_at),
+                "dataAnulareSplitTVA": date(&split.cancelled_at),
+                "statusSplitTVA": split.status,
+            },
+            "adresa_sediu_social": self.hq_address.to_anaf_json("s"),
+            "adresa_domiciliu_fiscal": self.fiscal_address.to_anaf_json("d"),
+        })
+    }
+}
+
+fn period(period: &VatPayerInterval) -> Map<String, Value> {
+    let fields = [
+        ("data_inceput_ScpTVA", json!(date(&period.from))),
+        ("data_sfarsit_ScpTVA", json!(date(&period.to))),
+        ("data_anul_imp_ScpTVA", json!(date(&period.cancelled_at))),
+        ("mesaj_ScpTVA", json!(text(&period.cancelled_reason))),
+    ];
+
+    fields
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+impl Address {
+    /// Renders the address with ANAF's field names, which start with `s` for the headquarters
+    /// and `d` for the fiscal domicile.
+    fn to_anaf_json(&self, prefix: &str) -> Value {
+        let fields = [
+            ("denumire_Strada", &self.street),
+            ("numar_Strada", &self.number),
+            ("denumire_Localitate", &self.town),
+            ("cod_Localitate", &self.town_code),
+            ("denumire_Judet", &self.county),
+            ("cod_Judet", &self.county_code),
+            ("cod_JudetAuto", &self.county_code_auto),
+            ("tara", &self.country),
+            ("detalii_Adresa", &self.details),
+            ("cod_Postal", &self.postal_code),
+        ];
+
+        fields
+            .into_iter()
+            .map(|(name, value)| (format!("{prefix}{name}"), json!(text(value))))
+            .collect::<Map<_, _>>()
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use serde_json::Value;
+
+    use crate::{parse_body, ToAnafJson};
+
+    use super::{
+        VatPayerGap, VatPayerInterval, VatPayerResponse, VatPayerResponseItem, VatScope,
+        VatScopeShape,
+    };
+
+    const RESPONSE: &str = r#"{
+        "cod": 200,
+        "message": "SUCCESS",
+        "found": [{
+            "date_generale": {
+                "cui": 49201783, "data": "2024-01-01", "denumire": "COMPANY SRL",
+                "adresa": "MUNICIPIUL BUCUREŞTI, SECTOR 1, STR. EXEMPLU, NR.1",
+                "nrRegCom": "J40/1/2020", "telefon": "", "fax": "", "codPostal": "010101",
+                "act": "", "stare_inregistrare": "INREGISTRAT din data 2020-01-01",
+                "data_inregistrare": "2020-01-01", "cod_CAEN": "6201", "iban": "",
+                "statusRO_e_Factura": true, "organFiscalCompetent": "", "forma_de_proprietate": "",
+                "forma_organizare": "", "forma_juridica": ""
+            },
+            "inregistrare_scop_Tva": {
+                "scpTVA": true,
+                "perioade_TVA": [{
+                    "data_inceput_ScpTVA": "2020-02-01", "data_sfarsit_ScpTVA": "",
+                    "data_anul_imp_ScpTVA": "", "mesaj_ScpTVA": ""
+                }]
+            },
+            "inregistrare_RTVAI": {
+                "dataInceputTvaInc": "", "dataSfarsitTvaInc": "", "dataActualizareTvaInc": "",
+                "dataPublicareTvaInc": "", "tipActTvaInc": "", "statusTvaIncasare": false
+            },
+            "stare_inactiv": {
+                "dataInactivare": "", "dataReactivare": "", "dataPublicare": "", "dataRadiere": "",
+                "statusInactivi": false
+            },
+            "inregistrare_SplitTVA": {
+                "dataInceputSplitTVA": "", "dataAnulareSplitTVA": "", "statusSplitTVA": false
+            },
+            "adresa_sediu_social": {
+                "sdenumire_Strada": "Str. Exemplu", "snumar_Strada": "1",
+                "sdenumire_Localitate": "Sector 1 Mun. Bucureşti", "scod_Localitate": "403",
+                "sdenumire_Judet": "MUNICIPIUL BUCUREŞTI", "scod_Judet": "40",
+                "scod_JudetAuto": "B", "stara": "", "sdetalii_Adresa": "", "scod_Postal": ""
+            },
+            "adresa_domiciliu_fiscal": {
+                "ddenumire_Strada": "Str. Exemplu", "dnumar_Strada": "1",
+                "ddenumire_Localitate": "Sector 1 Mun. Bucureşti", "dcod_Localitate": "403",
+                "ddenumire_Judet": "MUNICIPIUL BUCUREŞTI", "dcod_Judet": "40",
+                "dcod_JudetAuto": "B", "dtara": "", "ddetalii_Adresa": "", "dcod_Postal": ""
+            }
+        }],
+        "notFound": []
+    }"#;
+
+    #[test]
+    fn response_normalizes_placeholders() {
+        let response = parse_body::<VatPayerResponse>(RESPONSE.as_bytes()).unwrap();
+        let item = &response.data[0];
+
+        assert_eq!(item.company_info.fax, None);
+        assert_eq!(item.company_info.postal_code.as_deref(), Some("010101"));
+        assert_eq!(item.hq_address.country, None);
+        assert_eq!(item.fiscal_address.county_code_auto.as_deref(), Some("B"));
+
+        let expected = serde_json::from_str::<Value>(RESPONSE).unwrap();
+        assert_eq!(response.to_anaf_json(), expected);
+    }
+
+    #[test]
+    fn response_keeps_v7_shape() {
+        // v7 sends the latest period inline, and none of the fields added in v8
+        let mut body = serde_json::from_str::<Value>(RESPONSE).unwrap();
+        let item = &mut body["found"][0];
+
+        let general = item["date_generale"].as_object_mut().unwrap();
+        for field in [
+            "organFiscalCompetent",
+            "forma_de_proprietate",
+            "forma_organizare",
+            "forma_juridica",
+        ] {
+            general.remove(field);
+        }
+
+        let scope = item["inregistrare_scop_Tva"].as_object_mut().unwrap();
+        let Some(Value::Array(periods)) = scope.remove("perioade_TVA") else {
+            panic!("missing perioade_TVA");
+        };
+        scope.extend(periods[0].as_object().unwrap().clone());
+
+        let response = parse_body::<VatPayerResponse>(body.to_string().as_bytes()).unwrap();
+        assert_eq!(response.data[0].vat_scope.shape, VatScopeShape::Inline);
+        assert_eq!(response.data[0].vat_scope.periods.len(), 1);
+        assert_eq!(response.to_anaf_json(), body);
+    }
+
+    fn date(month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2021, month, 1).unwrap()
+    }
+
+    fn period(from: u32, to: Option<u32>) -> VatPayerInterval {
+        VatPayerInterval {
+            from: Some(date(from)),
+            to: to.map(date),
+            cancelled_at: None,
+            cancelled_reason: None,
+        }
+    }
+
+    fn item() -> VatPayerResponseItem {
+        let response = parse_body::<VatPayerResponse>(RESPONSE.as_bytes()).unwrap();
+        response.data.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn periods_answer_registration_on_date() {
+        let mut item = item();
+        item.vat_scope.periods = vec![period(9, None), period(2, Some(4)), period(3, Some(6))];
+
+        assert!(!item.was_vat_payer_on(date(1)));
+        assert!(item.was_vat_payer_on(date(5)));
+        assert!(!item.was_vat_payer_on(date(6)));
+        assert!(item.was_vat_payer_on(date(12)));
+        assert_eq!(
+            item.vat_payer_gaps(),
+            vec![VatPayerGap {
+                from: date(6),
+                to: Some(date(9)),
+            }]
+        );
+
+        item.vat_scope.periods.remove(0);
+        assert_eq!(
+            item.vat_payer_gaps().last(),
+            Some(&VatPayerGap {
+                from: date(6),
+                to: None,
+            })
+        );
+    }
+
+    #[test]
+    fn periods_read_v7_scope() {
+        let body = r#"{
+            "scpTVA": true, "data_inceput_ScpTVA": "2020-02-01", "data_sfarsit_ScpTVA": "",
+            "data_anul_imp_ScpTVA": "", "mesaj_ScpTVA": " "
+        }"#;
+        let scope = parse_body::<VatScope>(body.as_bytes()).unwrap();
+
+        assert_eq!(
+            scope.periods,
+            vec![VatPayerInterval {
+                from: NaiveDate::from_ymd_opt(2020, 2, 1),
+                to: None,
+                cancelled_at: None,
+                cancelled_reason: None,
+            }]
+        );
+
+        let scope = parse_body::<VatScope>(br#"{"scpTVA": false, "data_inceput_ScpTVA": ""}"#);
+        assert!(scope.unwrap().periods.is_empty());
+    }
 }
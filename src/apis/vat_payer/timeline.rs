@@ -0,0 +1,175 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use super::VatPayerResponseItem;
+
+/// Change in the fiscal status of a company.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum FiscalEventKind {
+    VatRegistered,
+    VatDeregistered,
+    /// Start of the VAT on collection system (TVA la încasare).
+    VatOnCollectionStarted,
+    VatOnCollectionEnded,
+    Inactivated,
+    Reactivated,
+    /// Removal from the trade registry (radiere).
+    Erased,
+    SplitVatStarted,
+    SplitVatCancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FiscalEvent {
+    pub date: NaiveDate,
+    pub kind: FiscalEventKind,
+}
+
+/// Fiscal status of a company on a given date, as returned by
+/// [`VatPayerResponseItem::fiscal_status_on`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FiscalStatus {
+    pub date: NaiveDate,
+    pub vat_payer: bool,
+    pub vat_on_collection: bool,
+    pub inactive: bool,
+    pub erased: bool,
+    pub split_vat: bool,
+}
+
+impl VatPayerResponseItem {
+    /// Merges the dates of the VAT registration periods, VAT on collection, inactivity and split
+    /// VAT into one list of events, oldest first.
+    ///
+    /// >>**Note**: ANAF only sends the latest VAT on collection, inactivity and split VAT dates, so older changes of those are not part of the timeline.
+    pub fn fiscal_timeline(&self) -> Vec<FiscalEvent> {
+        let income = &self.vat_payer_income;
+        let inactive = &self.inactive;
+        let split = &self.vat_split;
+
+        let vat_periods = self.vat_scope.periods.iter().flat_map(|period| {
+            [
+                (period.from, FiscalEventKind::VatRegistered),
+                (period.to, FiscalEventKind::VatDeregistered),
+            ]
+        });
+
+        let mut events = vat_periods
+            .chain([
+                (income.from, FiscalEventKind::VatOnCollectionStarted),
+                (income.to, FiscalEventKind::VatOnCollectionEnded),
+                (inactive.deactivated_at, FiscalEventKind::Inactivated),
+                (inactive.reactivated_at, FiscalEventKind::Reactivated),
+                (inactive.erased_at, FiscalEventKind::Erased),
+                (split.started_at, FiscalEventKind::SplitVatStarted),
+                (split.cancelled_at, FiscalEventKind::SplitVatCancelled),
+            ])
+            .filter_map(|(date, kind)| Some(FiscalEvent { date: date?, kind }))
+            .collect::<Vec<_>>();
+
+        events.sort_by_key(|event| (event.date, event.kind));
+        events
+    }
+
+    /// Returns every status flag of the company on the given date, following the same rules
+    /// as [`Self::fiscal_timeline`]. Changes take effect on the day they happen.
+    pub fn fiscal_status_on(&self, date: NaiveDate) -> FiscalStatus {
+        let income = &self.vat_payer_income;
+        let inactive = &self.inactive;
+        let split = &self.vat_split;
+
+        FiscalStatus {
+            date,
+            vat_payer: self.was_vat_payer_on(date),
+            vat_on_collection: between(income.from, income.to, date),
+            inactive: inactive.deactivated_at.is_some_and(|deactivated_at| {
+                let reactivated = inactive
+                    .reactivated_at
+                    .filter(|reactivated_at| *reactivated_at >= deactivated_at);
+
+                between(Some(deactivated_at), reactivated, date)
+            }),
+            erased: inactive
+                .erased_at
+                .is_some_and(|erased_at| erased_at <= date),
+            split_vat: between(split.started_at, split.cancelled_at, date),
+        }
+    }
+}
+
+/// Whether the date falls between a start and an optional end, which is exclusive.
+fn between(from: Option<NaiveDate>, to: Option<NaiveDate>, date: NaiveDate) -> bool {
+    from.is_some_and(|from| from <= date) && to.is_none_or(|to| date < to)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::{parse_body, vat_payer::VatPayerResponseItem};
+
+    use super::{FiscalEvent, FiscalEventKind};
+
+    const ITEM: &str = r#"{
+        "date_generale": {
+            "cui": 49201783, "denumire": "COMPANY SRL", "stare_inregistrare": "",
+            "statusRO_e_Factura": false
+        },
+        "inregistrare_scop_Tva": {"scpTVA": true, "perioade_TVA": [
+            {"data_inceput_ScpTVA": "2022-01-01", "data_sfarsit_ScpTVA": ""},
+            {"data_inceput_ScpTVA": "2020-01-01", "data_sfarsit_ScpTVA": "2021-01-01"}
+        ]},
+        "inregistrare_RTVAI": {
+            "dataInceputTvaInc": "2022-01-01", "dataSfarsitTvaInc": "2023-01-01",
+            "tipActTvaInc": "", "statusTvaIncasare": false
+        },
+        "stare_inactiv": {
+            "dataInactivare": "2020-06-01", "dataReactivare": "2020-09-01", "dataRadiere": "",
+            "statusInactivi": false
+        },
+        "inregistrare_SplitTVA": {
+            "dataInceputSplitTVA": "2020-06-01", "dataAnulareSplitTVA": "",
+            "statusSplitTVA": true
+        },
+        "adresa_sediu_social": {},
+        "adresa_domiciliu_fiscal": {}
+    }"#;
+
+    fn date(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+    }
+
+    #[test]
+    fn timeline_merges_status_blocks() {
+        let item = parse_body::<VatPayerResponseItem>(ITEM.as_bytes()).unwrap();
+
+        let events = item
+            .fiscal_timeline()
+            .into_iter()
+            .map(|FiscalEvent { date, kind }| (date, kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (date(2020, 1), FiscalEventKind::VatRegistered),
+                (date(2020, 6), FiscalEventKind::Inactivated),
+                (date(2020, 6), FiscalEventKind::SplitVatStarted),
+                (date(2020, 9), FiscalEventKind::Reactivated),
+                (date(2021, 1), FiscalEventKind::VatDeregistered),
+                (date(2022, 1), FiscalEventKind::VatRegistered),
+                (date(2022, 1), FiscalEventKind::VatOnCollectionStarted),
+                (date(2023, 1), FiscalEventKind::VatOnCollectionEnded),
+            ]
+        );
+
+        let status = item.fiscal_status_on(date(2020, 7));
+        assert!(status.vat_payer && status.inactive && status.split_vat);
+        assert!(!status.vat_on_collection && !status.erased);
+
+        let status = item.fiscal_status_on(date(2021, 6));
+        assert!(!status.vat_payer && !status.inactive && status.split_vat);
+
+        let status = item.fiscal_status_on(date(2022, 6));
+        assert!(status.vat_payer && status.vat_on_collection);
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    vat_payer::VatPayerEndpoint, AnafEndpoint, ApiRequest, ApiResponse, RawResponse, Result,
+};
+
+#[cfg(feature = "balance_api")]
+use crate::balance::{BalanceRequest, BalanceResponse};
+#[cfg(feature = "cults_api")]
+use crate::cults::CultEndpoint;
+#[cfg(feature = "farmers_api")]
+use crate::farmers::FarmerEndpoint;
+
+/// Blocking counterpart of [`crate::EndpointApi`].
+#[derive(Debug, Clone)]
+pub struct EndpointApi<E: AnafEndpoint> {
+    inner: crate::EndpointApi<E>,
+    runtime: Arc<Runtime>,
+}
+
+impl<E: AnafEndpoint> EndpointApi<E> {
+    pub fn new(inner: crate::EndpointApi<E>, runtime: Arc<Runtime>) -> Self {
+        Self { inner, runtime }
+    }
+
+    pub fn send(&self, request: Vec<E::Request>) -> Result<E::Response> {
+        self.runtime.block_on(self.inner.send(request))
+    }
+
+    pub fn send_raw(&self, request: Vec<E::Request>) -> Result<RawResponse<E::Response>> {
+        self.runtime.block_on(self.inner.send_raw(request))
+    }
+}
+
+impl<E, T> EndpointApi<E>
+where
+    E: AnafEndpoint<Request = ApiRequest, Response = ApiResponse<T>>,
+{
+    pub fn send_all(&self, request: Vec<ApiRequest>) -> Result<E::Response> {
+        self.runtime.block_on(self.inner.send_all(request))
+    }
+}
+
+/// Blocking counterpart of [`crate::vat_payer::VatPayerApi`].
+pub type VatPayerApi = EndpointApi<VatPayerEndpoint>;
+
+/// Blocking counterpart of [`crate::cults::CultApi`].
+#[cfg(feature = "cults_api")]
+pub type CultApi = EndpointApi<CultEndpoint>;
+
+/// Blocking counterpart of [`crate::farmers::FarmerApi`].
+#[cfg(feature = "farmers_api")]
+pub type FarmerApi = EndpointApi<FarmerEndpoint>;
+
+/// Blocking counterpart of [`crate::balance::BalanceApi`].
+#[cfg(feature = "balance_api")]
+#[derive(Debug, Clone)]
+pub struct BalanceApi {
+    inner: crate::balance::BalanceApi,
+    runtime: Arc<Runtime>,
+}
+
+#[cfg(feature = "balance_api")]
+impl BalanceApi {
+    pub fn new(inner: crate::balance::BalanceApi, runtime: Arc<Runtime>) -> Self {
+        Self { inner, runtime }
+    }
+
+    pub fn send(&self, request: BalanceRequest) -> Result<BalanceResponse> {
+        self.runtime.block_on(self.inner.send(request))
+    }
+
+    pub fn send_raw(&self, request: BalanceRequest) -> Result<RawResponse<BalanceResponse>> {
+        self.runtime.block_on(self.inner.send_raw(request))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use crate::{blocking::AnafClient, vat_payer::VatPayerApiVersion, ApiRequest, Cui};
+
+    #[test]
+    fn api_sends_without_runtime() {
+        let mut server = mockito::Server::new();
+
+        let version = VatPayerApiVersion::V8;
+        let endpoint = format!("/PlatitorTvaRest/api/{}/ws/tva", version);
+
+        let mock = server
+            .mock("POST", endpoint.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"cod\":200,\"message\":\"\",\"data\":[],\"not_found\":[]}")
+            .create();
+
+        let client = crate::AnafClient::builder()
+            .base_url(&server.url())
+            .build()
+            .and_then(AnafClient::from_async)
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(version).send(request);
+
+        assert!(response.is_ok());
+        mock.assert();
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{vat_payer::VatPayerApiVersion, AnafEndpoint, Result};
+
+#[cfg(feature = "balance_api")]
+use crate::balance::BalanceApiVersion;
+#[cfg(feature = "cults_api")]
+use crate::cults::CultApiVersion;
+#[cfg(feature = "farmers_api")]
+use crate::farmers::FarmerApiVersion;
+
+#[cfg(feature = "balance_api")]
+use super::BalanceApi;
+#[cfg(feature = "cults_api")]
+use super::CultApi;
+#[cfg(feature = "farmers_api")]
+use super::FarmerApi;
+use super::{EndpointApi, VatPayerApi};
+
+/// Blocking Anaf API Client
+///
+/// Wraps an [`crate::AnafClient`] together with a private runtime which drives its requests,
+/// so the callers don't need one.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # use anaf_api::{blocking::AnafClient, vat_payer::VatPayerApiVersion, ApiRequest};
+/// # fn main() -> anaf_api::Result<()> {
+/// let client = AnafClient::new();
+/// let now = chrono::Local::now().date_naive();
+///
+/// let request = vec![ApiRequest::new("RO49201783".parse()?, now)];
+/// let response = client.vat_payer(VatPayerApiVersion::V8).send(request)?;
+///
+/// dbg!(&response);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Use [`AnafClient::from_async`] to configure it with [`crate::AnafClientBuilder`].
+#[derive(Debug, Clone)]
+pub struct AnafClient {
+    inner: crate::AnafClient,
+    runtime: Arc<Runtime>,
+}
+
+impl Default for AnafClient {
+    fn default() -> Self {
+        Self::from_async(crate::AnafClient::new()).expect("failed to start the blocking runtime")
+    }
+}
+
+impl AnafClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps an async client, keeping its configuration.
+    pub fn from_async(inner: crate::AnafClient) -> Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+}
+
+impl AnafClient {
+    /// Initiates the API of any [`AnafEndpoint`].
+    pub fn endpoint<E: AnafEndpoint>(&self, version: E::Version) -> EndpointApi<E> {
+        EndpointApi::new(self.inner.endpoint(version), self.runtime.clone())
+    }
+
+    /// Initiates the VatPayer API.
+    pub fn vat_payer(&self, version: VatPayerApiVersion) -> VatPayerApi {
+        VatPayerApi::new(self.inner.vat_payer(version), self.runtime.clone())
+    }
+
+    /// Initiates the Cult API.
+    #[cfg(feature = "cults_api")]
+    pub fn cult(&self, version: CultApiVersion) -> CultApi {
+        CultApi::new(self.inner.cult(version), self.runtime.clone())
+    }
+
+    /// Initiates the Farmer API.
+    #[cfg(feature = "farmers_api")]
+    pub fn farmer(&self, version: FarmerApiVersion) -> FarmerApi {
+        FarmerApi::new(self.inner.farmer(version), self.runtime.clone())
+    }
+
+    #[cfg(feature = "balance_api")]
+    pub fn balance(&self, version: BalanceApiVersion) -> BalanceApi {
+        BalanceApi::new(self.inner.balance(version), self.runtime.clone())
+    }
+}
@@ -0,0 +1,11 @@
+//! Blocking ANAF API client.
+//!
+//! Mirrors [`crate::AnafClient`] and its APIs for code that doesn't run inside an async
+//! runtime. The requests, responses and policies are the same as for the async client.
+//!
+//! >>**Note**: the blocking client must not be used from within an async runtime.
+mod api;
+mod client;
+
+pub use api::*;
+pub use client::*;
@@ -0,0 +1,249 @@
+use std::{
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{balance::BalanceRawResponse, Cui, Result};
+
+use super::MemoryCache;
+
+/// Storage for balance responses, keyed by (CUI, year).
+///
+/// The responses are stored as returned by ANAF, so they can be parsed again by newer versions
+/// of this crate.
+pub trait BalanceStore: Debug + Send + Sync {
+    fn get(&self, registration_code: Cui, year: usize) -> Result<Option<BalanceRawResponse>>;
+
+    fn put(&self, response: &BalanceRawResponse) -> Result<()>;
+}
+
+/// Balance responses, cached in memory per (CUI, year).
+pub type BalanceCache = MemoryCache<(Cui, usize), BalanceRawResponse>;
+
+impl BalanceStore for BalanceCache {
+    fn get(&self, registration_code: Cui, year: usize) -> Result<Option<BalanceRawResponse>> {
+        Ok(MemoryCache::get(self, &(registration_code, year)))
+    }
+
+    fn put(&self, response: &BalanceRawResponse) -> Result<()> {
+        self.insert(
+            (response.unique_registration_code, response.year),
+            response.clone(),
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StoredBalance {
+    stored_at: DateTime<Utc>,
+    response: BalanceRawResponse,
+}
+
+/// Stores balance responses as JSON files, one per (CUI, year), so they survive restarts.
+///
+/// Published financial statements rarely change, so stored balances never expire, except for
+/// the most recent years which ANAF still amends. By default, the balances of the current and
+/// the previous year are fetched again one day after they were stored.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # fn main() -> anaf_api::Result<()> {
+/// use std::time::Duration;
+///
+/// use anaf_api::{AnafClient, FileBalanceStore};
+///
+/// let store = FileBalanceStore::new("/var/cache/anaf/balance")?
+///     .with_recent_years(2)
+///     .with_recent_ttl(Duration::from_secs(7 * 24 * 60 * 60));
+///
+/// let client = AnafClient::builder().balance_store(store).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileBalanceStore {
+    directory: PathBuf,
+    recent_years: usize,
+    recent_ttl: Duration,
+}
+
+impl FileBalanceStore {
+    /// Uses the given directory, creating it if it doesn't exist.
+    pub fn new(directory: impl AsRef<Path>) -> Result<Self> {
+        fs::create_dir_all(directory.as_ref())?;
+
+        Ok(Self {
+            directory: directory.as_ref().to_owned(),
+            recent_years: 2,
+            recent_ttl: Duration::from_secs(24 * 60 * 60),
+        })
+    }
+
+    /// Sets how many years, counting back from the current one, are still amended by ANAF.
+    pub fn with_recent_years(mut self, recent_years: usize) -> Self {
+        self.recent_years = recent_years;
+        self
+    }
+
+    /// Sets how long the balances of the recent years are served before being fetched again.
+    pub fn with_recent_ttl(mut self, recent_ttl: Duration) -> Self {
+        self.recent_ttl = recent_ttl;
+        self
+    }
+
+    /// Removes the stored balance of a company for the given year.
+    pub fn invalidate(&self, registration_code: Cui, year: usize) -> Result<()> {
+        match fs::remove_file(self.path(registration_code, year)) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, registration_code: Cui, year: usize) -> PathBuf {
+        self.directory
+            .join(format!("{}-{}.json", registration_code, year))
+    }
+
+    fn is_recent(&self, year: usize) -> bool {
+        let current_year = Utc::now().year() as usize;
+
+        year + self.recent_years > current_year
+    }
+}
+
+impl BalanceStore for FileBalanceStore {
+    fn get(&self, registration_code: Cui, year: usize) -> Result<Option<BalanceRawResponse>> {
+        let contents = match fs::read(self.path(registration_code, year)) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let stored = serde_json::from_slice::<StoredBalance>(&contents)?;
+
+        if self.is_recent(year) {
+            let age = (Utc::now() - stored.stored_at).to_std().unwrap_or_default();
+
+            if age >= self.recent_ttl {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(stored.response))
+    }
+
+    fn put(&self, response: &BalanceRawResponse) -> Result<()> {
+        let path = self.path(response.unique_registration_code, response.year);
+        let stored = StoredBalance {
+            stored_at: Utc::now(),
+            response: response.clone(),
+        };
+
+        // write to a temporary file first, so readers never see a partial file, named uniquely so
+        // concurrent writers, in this process or another one, don't write over each other's
+        static NEXT_WRITE: AtomicU64 = AtomicU64::new(0);
+        let temporary = path.with_extension(format!(
+            "json.{}-{}.tmp",
+            std::process::id(),
+            NEXT_WRITE.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let contents = serde_json::to_vec(&stored)?;
+        if let Err(error) =
+            fs::write(&temporary, contents).and_then(|_| fs::rename(&temporary, path))
+        {
+            let _ = fs::remove_file(&temporary);
+            return Err(error.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::{Datelike, Utc};
+
+    use crate::{balance::BalanceRawResponse, Cui};
+
+    use super::{BalanceStore, FileBalanceStore};
+
+    fn cui() -> Cui {
+        Cui::new(40914732).unwrap()
+    }
+
+    fn response(year: usize) -> BalanceRawResponse {
+        BalanceRawResponse {
+            year,
+            unique_registration_code: cui(),
+            name: "COMPANY SRL".to_owned(),
+            activity_code: 6201,
+            activity_name: "Activitati de realizare a soft-ului la comanda".to_owned(),
+            balance: vec![],
+        }
+    }
+
+    fn store(name: &str) -> FileBalanceStore {
+        let directory =
+            std::env::temp_dir().join(format!("anaf-api-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+
+        FileBalanceStore::new(directory).unwrap()
+    }
+
+    #[test]
+    fn store_keeps_published_years() {
+        let store = store("published").with_recent_ttl(Duration::ZERO);
+        store.put(&response(2019)).unwrap();
+
+        let stored = store.get(cui(), 2019).unwrap().unwrap();
+        assert_eq!(stored.name, "COMPANY SRL");
+        assert!(store.get(cui(), 2020).unwrap().is_none());
+
+        store.invalidate(cui(), 2019).unwrap();
+        assert!(store.get(cui(), 2019).unwrap().is_none());
+    }
+
+    #[test]
+    fn store_expires_recent_years() {
+        let store = store("recent").with_recent_ttl(Duration::ZERO);
+        let current_year = Utc::now().year() as usize;
+
+        store.put(&response(current_year - 1)).unwrap();
+        assert!(store.get(cui(), current_year - 1).unwrap().is_none());
+
+        let store = store.with_recent_ttl(Duration::from_secs(60));
+        assert!(store.get(cui(), current_year - 1).unwrap().is_some());
+    }
+
+    #[test]
+    fn store_survives_concurrent_writers() {
+        let store = store("concurrent");
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        store.put(&response(2019)).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert!(store.get(cui(), 2019).unwrap().is_some());
+
+        let files = std::fs::read_dir(&store.directory).unwrap().count();
+        assert_eq!(files, 1);
+    }
+}
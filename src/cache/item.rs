@@ -0,0 +1,273 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use chrono::NaiveDate;
+
+use crate::{AnafEndpoint, ApiRequest, ApiResponse, Cui};
+
+use super::{CacheConfig, MemoryCache};
+
+/// Company returned by a registry lookup.
+pub trait RegistryItem: Clone + Debug + Send + Sync + 'static {
+    fn registration_code(&self) -> Cui;
+
+    /// Date the company was looked up for, as echoed back by ANAF.
+    fn query_date(&self) -> Option<NaiveDate>;
+}
+
+/// Cache used by [`crate::EndpointApi`] to skip the companies it already knows about.
+pub trait EndpointCache<E: AnafEndpoint>: Debug + Send + Sync {
+    /// Splits the request into the companies which still have to be fetched and the response
+    /// built from the cached ones, if any.
+    fn lookup(
+        &self,
+        version: &E::Version,
+        request: Vec<E::Request>,
+    ) -> (Vec<E::Request>, Option<E::Response>);
+
+    /// Stores the companies of a fetched response.
+    fn store(&self, version: &E::Version, request: &[E::Request], response: &E::Response);
+
+    /// Merges the cached part of a response into the fetched one.
+    fn merge(&self, cached: E::Response, fetched: E::Response) -> E::Response;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ItemKey {
+    pub registration_code: Cui,
+    pub when: NaiveDate,
+    pub version: String,
+}
+
+/// Caches registry companies per (CUI, date, API version).
+#[derive(Debug)]
+pub struct ItemCache<T> {
+    inner: MemoryCache<ItemKey, T>,
+}
+
+impl<T: RegistryItem> ItemCache<T> {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            inner: MemoryCache::new(config),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.inner.clear()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<E, T> EndpointCache<E> for ItemCache<T>
+where
+    E: AnafEndpoint<Request = ApiRequest, Response = ApiResponse<T>>,
+    T: RegistryItem,
+{
+    fn lookup(
+        &self,
+        version: &E::Version,
+        request: Vec<ApiRequest>,
+    ) -> (Vec<ApiRequest>, Option<ApiResponse<T>>) {
+        let version = version.to_string();
+        let mut found = vec![];
+
+        let missing = request
+            .into_iter()
+            .filter(|it| {
+                let key = ItemKey {
+                    registration_code: it.registration_code,
+                    when: it.when,
+                    version: version.clone(),
+                };
+
+                match self.inner.get(&key) {
+                    Some(item) => {
+                        found.push(item);
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect();
+
+        if found.is_empty() {
+            return (missing, None);
+        }
+
+        tracing::debug!("Serving {} companies from the cache", found.len());
+
+        let cached = ApiResponse {
+            status: 200,
+            message: String::new(),
+            data: found,
+            not_found: vec![],
+        };
+
+        (missing, Some(cached))
+    }
+
+    fn store(&self, version: &E::Version, request: &[ApiRequest], response: &ApiResponse<T>) {
+        let version = version.to_string();
+        let mut dates = HashMap::<Cui, Vec<NaiveDate>>::new();
+        for it in request {
+            dates.entry(it.registration_code).or_default().push(it.when);
+        }
+
+        for item in &response.data {
+            let Some(requested) = dates.get(&item.registration_code()) else {
+                continue;
+            };
+
+            // the same company can be asked for on several dates, so each item is keyed by the
+            // date ANAF echoes back, and only falls back to the request when that is unambiguous
+            let when = match (item.query_date(), requested.as_slice()) {
+                (Some(when), _) if requested.contains(&when) => when,
+                (None, [when]) => *when,
+                _ => continue,
+            };
+
+            let key = ItemKey {
+                registration_code: item.registration_code(),
+                when,
+                version: version.clone(),
+            };
+
+            self.inner.insert(key, item.clone());
+        }
+    }
+
+    fn merge(&self, cached: ApiResponse<T>, mut fetched: ApiResponse<T>) -> ApiResponse<T> {
+        fetched.merge(cached);
+        fetched
+    }
+}
+
+#[cfg(all(test, feature = "cults_api"))]
+mod test {
+    use chrono::NaiveDate;
+    use reqwest::Method;
+
+    use crate::{AnafClient, ApiRequest, CacheConfig, Cui, HttpResponse, InMemoryTransport};
+
+    const ENDPOINT: &str = "/RegCult/api/v2/ws/cult";
+
+    fn item(registration_code: Cui) -> String {
+        item_on(registration_code, "2024-01-01")
+    }
+
+    fn item_on(registration_code: Cui, when: &str) -> String {
+        format!(
+            r#"{{"cui":{registration_code},"data":"{when}","denumire":"","adresa":"","nrRegCom":"","telefon":"","fax":"","codPostal":"","act":"","stare_inregistrare":"","dataInceputRegCult":"","dataAnulareRegCult":null,"statusRegCult":true}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn cache_sends_only_missing_companies() {
+        let (first, second) = (Cui::from_base(1), Cui::from_base(2));
+        let transport = InMemoryTransport::new();
+        transport.respond(
+            Method::POST,
+            ENDPOINT,
+            HttpResponse::json(format!(
+                r#"{{"cod":200,"message":"","found":[{}],"notFound":[]}}"#,
+                item(first)
+            )),
+        );
+        transport.respond(
+            Method::POST,
+            ENDPOINT,
+            HttpResponse::json(format!(
+                r#"{{"cod":200,"message":"","found":[{}],"notFound":[]}}"#,
+                item(second)
+            )),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .cache(CacheConfig::default())
+            .build()
+            .unwrap();
+
+        let when = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let api = client.cult(Default::default());
+
+        api.send(vec![ApiRequest::new(first, when)]).await.unwrap();
+
+        let response = api
+            .send(vec![
+                ApiRequest::new(first, when),
+                ApiRequest::new(second, when),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(response.data.len(), 2);
+
+        let response = api
+            .send(vec![
+                ApiRequest::new(first, when),
+                ApiRequest::new(second, when),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(response.data.len(), 2);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].body.as_deref(),
+            Some(&br#"[{"cui":27,"data":"2024-01-01"}]"#[..])
+        );
+    }
+
+    #[tokio::test]
+    async fn cache_keys_companies_by_their_date() {
+        let company = Cui::from_base(1);
+        let transport = InMemoryTransport::new();
+        transport.respond(
+            Method::POST,
+            ENDPOINT,
+            HttpResponse::json(format!(
+                r#"{{"cod":200,"message":"","found":[{},{}],"notFound":[]}}"#,
+                item_on(company, "2024-01-01"),
+                item_on(company, "2024-02-01")
+            )),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .cache(CacheConfig::default())
+            .build()
+            .unwrap();
+
+        let january = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let february = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        let api = client.cult(Default::default());
+
+        let request = vec![
+            ApiRequest::new(company, january),
+            ApiRequest::new(company, february),
+        ];
+        api.send(request.clone()).await.unwrap();
+
+        let mut dates = api
+            .send(request)
+            .await
+            .unwrap()
+            .data
+            .into_iter()
+            .map(|it| it.when)
+            .collect::<Vec<_>>();
+        dates.sort();
+
+        assert_eq!(dates, vec![Some(january), Some(february)]);
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
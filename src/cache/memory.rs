@@ -0,0 +1,184 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::Mutex,
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// Settings of the in-memory caches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How long an entry is served after it was fetched.
+    pub ttl: Duration,
+    /// How many entries each cache keeps, dropping the oldest ones first.
+    pub capacity: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60 * 60),
+            capacity: 10_000,
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self { ttl, capacity }
+    }
+}
+
+#[derive(Debug)]
+struct Entries<K, V> {
+    values: HashMap<K, Entry<V>>,
+    // keys in insertion order, used to drop the oldest entries when the cache is full
+    order: VecDeque<(K, u64)>,
+    next_position: u64,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    inserted_at: Instant,
+    position: u64,
+}
+
+/// In-memory cache with a time to live and a maximum capacity.
+#[derive(Debug)]
+pub struct MemoryCache<K, V> {
+    config: CacheConfig,
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K, V> MemoryCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(Entries {
+                values: HashMap::new(),
+                order: VecDeque::new(),
+                next_position: 0,
+            }),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().expect("cache lock is poisoned");
+
+        match entries.values.get(key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.config.ttl => {
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.values.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if self.config.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().expect("cache lock is poisoned");
+        let position = entries.next_position;
+        entries.next_position += 1;
+
+        entries.values.insert(
+            key.clone(),
+            Entry {
+                value,
+                inserted_at: Instant::now(),
+                position,
+            },
+        );
+        entries.order.push_back((key, position));
+
+        while entries.values.len() > self.config.capacity {
+            let Some((key, position)) = entries.order.pop_front() else {
+                break;
+            };
+
+            // the key may have been replaced since, in which case this is a stale position
+            if entries.values.get(&key).map(|entry| entry.position) == Some(position) {
+                entries.values.remove(&key);
+            }
+        }
+
+        // keep the order queue from growing with stale positions of replaced keys
+        if entries.order.len() > self.config.capacity.saturating_mul(2) {
+            let Entries { values, order, .. } = &mut *entries;
+            order.retain(|(key, position)| {
+                values.get(key).map(|entry| entry.position) == Some(*position)
+            });
+        }
+    }
+
+    pub fn remove(&self, key: &K) {
+        self.entries
+            .lock()
+            .expect("cache lock is poisoned")
+            .values
+            .remove(key);
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().expect("cache lock is poisoned");
+        entries.values.clear();
+        entries.order.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .expect("cache lock is poisoned")
+            .values
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{CacheConfig, MemoryCache};
+
+    #[tokio::test(start_paused = true)]
+    async fn cache_expires_entries() {
+        let cache = MemoryCache::new(CacheConfig::new(Duration::from_secs(10), 10));
+        cache.insert(1, "a");
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(cache.get(&1), Some("a"));
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(cache.get(&1), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cache_drops_oldest_entries() {
+        let cache = MemoryCache::new(CacheConfig::new(Duration::from_secs(10), 2));
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.insert(1, "c");
+        cache.insert(3, "d");
+
+        assert_eq!(cache.get(&1), Some("c"));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some("d"));
+        assert_eq!(cache.len(), 2);
+    }
+}
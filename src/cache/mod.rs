@@ -0,0 +1,40 @@
+#[cfg(feature = "balance_api")]
+mod balance;
+mod item;
+mod memory;
+
+#[cfg(feature = "balance_api")]
+pub use balance::*;
+pub use item::*;
+pub use memory::*;
+
+use std::sync::Arc;
+
+use crate::vat_payer::VatPayerResponseItem;
+
+#[cfg(feature = "cults_api")]
+use crate::cults::CultResponseItem;
+#[cfg(feature = "farmers_api")]
+use crate::farmers::FarmerResponseItem;
+
+/// The caches shared by all the API handles created from one client.
+#[derive(Debug)]
+pub(crate) struct Caches {
+    pub vat_payer: Arc<ItemCache<VatPayerResponseItem>>,
+    #[cfg(feature = "cults_api")]
+    pub cult: Arc<ItemCache<CultResponseItem>>,
+    #[cfg(feature = "farmers_api")]
+    pub farmer: Arc<ItemCache<FarmerResponseItem>>,
+}
+
+impl Caches {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            vat_payer: Arc::new(ItemCache::new(config)),
+            #[cfg(feature = "cults_api")]
+            cult: Arc::new(ItemCache::new(config)),
+            #[cfg(feature = "farmers_api")]
+            farmer: Arc::new(ItemCache::new(config)),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use reqwest::{Client, Proxy};
+
+use crate::{ApiFamily, CacheConfig, Caches, ReqwestTransport, Result, Transport};
+
+#[cfg(feature = "balance_api")]
+use crate::{BalanceCache, BalanceStore};
+
+use super::{
+    AnafClient, CircuitBreaker, CircuitBreakerConfig, RateLimit, RateLimiter, RetryPolicy,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://webservicesp.anaf.ro";
+
+/// Anaf API Client Builder
+///
+/// Configures the [`AnafClient`] before creating it.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # fn main() -> anaf_api::Result<()> {
+/// use std::time::Duration;
+///
+/// use anaf_api::AnafClient;
+///
+/// let client = AnafClient::builder()
+///     .base_url("http://localhost:8080")
+///     .connect_timeout(Duration::from_secs(5))
+///     .timeout(Duration::from_secs(30))
+///     .user_agent("my-service/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// >>**Note**: the timeouts, user agent and proxy only apply to the client built by default.
+#[derive(Debug)]
+pub struct AnafClientBuilder {
+    base_url: String,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<Proxy>,
+    client: Option<Client>,
+    transport: Option<Arc<dyn Transport>>,
+    rate_limits: HashMap<ApiFamily, Option<RateLimit>>,
+    circuit_breakers: HashMap<ApiFamily, CircuitBreakerConfig>,
+    retry_policy: RetryPolicy,
+    cache: Option<CacheConfig>,
+    #[cfg(feature = "balance_api")]
+    balance_store: Option<Arc<dyn BalanceStore>>,
+}
+
+impl Default for AnafClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            connect_timeout: None,
+            timeout: None,
+            user_agent: None,
+            proxy: None,
+            client: None,
+            transport: None,
+            rate_limits: ApiFamily::all()
+                .into_iter()
+                .map(|family| (family, Some(RateLimit::default())))
+                .collect(),
+            circuit_breakers: HashMap::new(),
+            retry_policy: RetryPolicy::none(),
+            cache: None,
+            #[cfg(feature = "balance_api")]
+            balance_store: None,
+        }
+    }
+}
+
+impl AnafClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the base URL of the ANAF web services, e.g. a staging server or a local mock.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Sets the timeout for the connect phase of every request.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the total timeout of every request, from connecting until the body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Routes every request through the given proxy.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Uses an existing [`reqwest::Client`] instead of building a new one.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sends the requests through the given transport instead of a [`reqwest::Client`].
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Sets the rate limit of every endpoint family.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        for family in ApiFamily::all() {
+            self.rate_limits.insert(family, Some(limit));
+        }
+        self
+    }
+
+    /// Sets the rate limit of a single endpoint family.
+    pub fn family_rate_limit(mut self, family: ApiFamily, limit: RateLimit) -> Self {
+        self.rate_limits.insert(family, Some(limit));
+        self
+    }
+
+    /// Disables client-side rate limiting for every endpoint family.
+    pub fn without_rate_limit(mut self) -> Self {
+        for family in ApiFamily::all() {
+            self.rate_limits.insert(family, None);
+        }
+        self
+    }
+
+    /// Stops calling every endpoint family while it keeps failing, e.g. during ANAF's maintenance
+    /// windows.
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        for family in ApiFamily::all() {
+            self.circuit_breakers.insert(family, config);
+        }
+        self
+    }
+
+    /// Sets the circuit breaker of a single endpoint family.
+    pub fn family_circuit_breaker(
+        mut self,
+        family: ApiFamily,
+        config: CircuitBreakerConfig,
+    ) -> Self {
+        self.circuit_breakers.insert(family, config);
+        self
+    }
+
+    /// Sets the retry policy shared by every API.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Caches the responses of the VAT payer, cult, farmer and balance APIs in memory.
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Stores the balance responses in the given store, e.g. a [`crate::FileBalanceStore`] which
+    /// keeps them across restarts. Takes precedence over the in-memory cache.
+    #[cfg(feature = "balance_api")]
+    pub fn balance_store(mut self, store: impl BalanceStore + 'static) -> Self {
+        self.balance_store = Some(Arc::new(store));
+        self
+    }
+
+    pub fn build(self) -> Result<AnafClient> {
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let client = match self.client {
+                    Some(client) => client,
+                    None => {
+                        let mut builder = Client::builder();
+
+                        if let Some(timeout) = self.connect_timeout {
+                            builder = builder.connect_timeout(timeout);
+                        }
+
+                        if let Some(timeout) = self.timeout {
+                            builder = builder.timeout(timeout);
+                        }
+
+                        if let Some(user_agent) = self.user_agent {
+                            builder = builder.user_agent(user_agent);
+                        }
+
+                        if let Some(proxy) = self.proxy {
+                            builder = builder.proxy(proxy);
+                        }
+
+                        builder.build()?
+                    }
+                };
+
+                Arc::new(ReqwestTransport::new(client))
+            }
+        };
+
+        let rate_limiters = self
+            .rate_limits
+            .into_iter()
+            .filter_map(|(family, limit)| limit.map(|limit| (family, RateLimiter::new(limit))))
+            .collect();
+
+        let circuit_breakers = self
+            .circuit_breakers
+            .into_iter()
+            .map(|(family, config)| (family, CircuitBreaker::new(family, config)))
+            .collect();
+
+        Ok(AnafClient {
+            base_url: self.base_url.into(),
+            transport,
+            rate_limiters: Arc::new(rate_limiters),
+            circuit_breakers: Arc::new(circuit_breakers),
+            retry_policy: self.retry_policy,
+            caches: self.cache.map(|config| Arc::new(Caches::new(config))),
+            coalescers: Default::default(),
+            #[cfg(feature = "balance_api")]
+            balance_store: self.balance_store.or_else(|| {
+                self.cache
+                    .map(|config| Arc::new(BalanceCache::new(config)) as Arc<dyn BalanceStore>)
+            }),
+        })
+    }
+}
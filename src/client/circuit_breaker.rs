@@ -0,0 +1,302 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{record_circuit_state, ApiError, ApiFamily, Result};
+
+/// Circuit breaker settings.
+///
+/// The circuit opens after `failure_threshold` failures in a row, and lets one probe request
+/// through once `cool_down` has passed. Only failures which may go away later, such as
+/// maintenance windows, throttling and timeouts, count towards the threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: u32,
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(60),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn new(failure_threshold: u32, cool_down: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cool_down,
+        }
+    }
+}
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go out normally.
+    Closed,
+    /// ANAF is considered unavailable, and requests fail fast.
+    Open,
+    /// The cool-down has passed, and a probe request checks whether ANAF is back.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    failures: u32,
+    opened_at: Option<Instant>,
+    probing: bool,
+}
+
+/// Circuit breaker of one endpoint family.
+///
+/// Clones share the same circuit, so every API handle created from one [`crate::AnafClient`]
+/// stops calling ANAF together. Open circuits fail with [`ApiError::CircuitOpen`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    family: ApiFamily,
+    config: CircuitBreakerConfig,
+    circuit: Arc<Mutex<Circuit>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(family: ApiFamily, config: CircuitBreakerConfig) -> Self {
+        Self {
+            family,
+            config,
+            circuit: Arc::new(Mutex::new(Circuit {
+                failures: 0,
+                opened_at: None,
+                probing: false,
+            })),
+        }
+    }
+
+    pub fn config(&self) -> CircuitBreakerConfig {
+        self.config
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let circuit = self
+            .circuit
+            .lock()
+            .expect("circuit breaker lock is poisoned");
+
+        match circuit.opened_at {
+            None => CircuitState::Closed,
+            Some(_) if circuit.probing => CircuitState::HalfOpen,
+            Some(opened_at) if opened_at.elapsed() >= self.config.cool_down => {
+                CircuitState::HalfOpen
+            }
+            Some(_) => CircuitState::Open,
+        }
+    }
+
+    /// Runs the call if the circuit allows it, and records its outcome.
+    pub async fn call<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        let permit = self.acquire()?;
+        let result = call.await;
+        permit.record(&result);
+
+        result
+    }
+
+    /// Lets the request through, unless the circuit is open or a probe is already in flight.
+    fn acquire(&self) -> Result<Permit<'_>> {
+        let mut circuit = self
+            .circuit
+            .lock()
+            .expect("circuit breaker lock is poisoned");
+
+        let Some(opened_at) = circuit.opened_at else {
+            return Ok(Permit {
+                breaker: self,
+                probe: false,
+            });
+        };
+
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.config.cool_down || circuit.probing {
+            return Err(ApiError::CircuitOpen {
+                family: self.family,
+                retry_after: self.config.cool_down.checked_sub(elapsed),
+            });
+        }
+
+        tracing::debug!("Probing whether the {} API is available again", self.family);
+        circuit.probing = true;
+
+        Ok(Permit {
+            breaker: self,
+            probe: true,
+        })
+    }
+
+    fn succeed(&self) {
+        let mut circuit = self
+            .circuit
+            .lock()
+            .expect("circuit breaker lock is poisoned");
+
+        if circuit.opened_at.is_some() {
+            tracing::info!("The {} API is available again", self.family);
+            record_circuit_state(self.family, false);
+        }
+
+        circuit.failures = 0;
+        circuit.opened_at = None;
+        circuit.probing = false;
+    }
+
+    fn fail(&self, probe: bool) {
+        let mut circuit = self
+            .circuit
+            .lock()
+            .expect("circuit breaker lock is poisoned");
+        circuit.failures += 1;
+
+        let opens = match circuit.opened_at {
+            None => circuit.failures >= self.config.failure_threshold,
+            Some(_) => probe,
+        };
+
+        if opens {
+            if circuit.opened_at.is_none() {
+                tracing::warn!(
+                    "The {} API failed {} times in a row, pausing it for {:?}",
+                    self.family,
+                    circuit.failures,
+                    self.config.cool_down
+                );
+                record_circuit_state(self.family, true);
+            }
+
+            circuit.opened_at = Some(Instant::now());
+            circuit.probing = false;
+        }
+    }
+}
+
+/// Allows one request through the circuit breaker. A probe which is dropped before its outcome
+/// is recorded, e.g. when the call is cancelled, lets the next request probe instead.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl Permit<'_> {
+    fn record<T>(self, result: &Result<T>) {
+        match result {
+            Err(error) if error.is_retryable() => self.breaker.fail(self.probe),
+            _ => self.breaker.succeed(),
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            self.breaker
+                .circuit
+                .lock()
+                .expect("circuit breaker lock is poisoned")
+                .probing = false;
+        }
+    }
+}
+
+/// Runs the call through the circuit breaker, if there is one.
+pub(crate) async fn guard<T>(
+    breaker: Option<&CircuitBreaker>,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match breaker {
+        Some(breaker) => breaker.call(call).await,
+        None => call.await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use reqwest::{Method, StatusCode};
+
+    use crate::{
+        AnafClient, ApiError, ApiFamily, ApiRequest, Cui, HttpResponse, InMemoryTransport,
+    };
+
+    use super::{CircuitBreakerConfig, CircuitState};
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_opens_and_probes() {
+        let transport = InMemoryTransport::new();
+        let path = "/PlatitorTvaRest/api/v8/ws/tva";
+        for _ in 0..3 {
+            transport.respond(
+                Method::POST,
+                path,
+                HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            );
+        }
+        transport.respond(
+            Method::POST,
+            path,
+            HttpResponse::json(r#"{"cod":200,"message":"","found":[],"notFound":[111111115]}"#),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .circuit_breaker(CircuitBreakerConfig::new(2, Duration::from_secs(60)))
+            .build()
+            .unwrap();
+        let api = client.vat_payer(Default::default());
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+
+        for _ in 0..2 {
+            assert!(api.send(request.clone()).await.is_err());
+        }
+        assert_eq!(
+            client.circuit_state(ApiFamily::VatPayer),
+            Some(CircuitState::Open)
+        );
+
+        let response = api.send(request.clone()).await;
+        assert!(matches!(response, Err(ApiError::CircuitOpen { .. })));
+        assert_eq!(transport.requests().len(), 2);
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert_eq!(
+            client.circuit_state(ApiFamily::VatPayer),
+            Some(CircuitState::HalfOpen)
+        );
+        assert!(api.send(request.clone()).await.is_err());
+        assert_eq!(
+            client.circuit_state(ApiFamily::VatPayer),
+            Some(CircuitState::Open)
+        );
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert!(api.send(request).await.is_ok());
+        assert_eq!(
+            client.circuit_state(ApiFamily::VatPayer),
+            Some(CircuitState::Closed)
+        );
+        assert_eq!(
+            client.circuit_state(ApiFamily::Balance),
+            Some(CircuitState::Closed)
+        );
+    }
+}
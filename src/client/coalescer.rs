@@ -0,0 +1,463 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::watch;
+
+use crate::{AnafEndpoint, ApiError, ApiRequest, ApiResponse, Cui, ItemKey, RegistryItem, Result};
+
+#[cfg(feature = "cults_api")]
+use crate::cults::CultResponseItem;
+#[cfg(feature = "farmers_api")]
+use crate::farmers::FarmerResponseItem;
+use crate::vat_payer::VatPayerResponseItem;
+
+pub type FetchFuture<'a, R> = Pin<Box<dyn Future<Output = Result<R>> + Send + 'a>>;
+
+/// Sends the part of a request which is not in flight yet.
+pub type Fetch<'a, E> = Box<
+    dyn Fn(Vec<<E as AnafEndpoint>::Request>) -> FetchFuture<'a, <E as AnafEndpoint>::Response>
+        + Send
+        + Sync
+        + 'a,
+>;
+
+/// Shares the upstream requests of concurrent [`crate::EndpointApi`] calls.
+pub trait EndpointCoalescer<E: AnafEndpoint>: Debug + Send + Sync {
+    /// Fetches the request, waiting for the companies which another call is already fetching
+    /// instead of asking ANAF for them again.
+    fn send<'a>(
+        &'a self,
+        version: &'a E::Version,
+        request: Vec<E::Request>,
+        fetch: Fetch<'a, E>,
+    ) -> FetchFuture<'a, E::Response>;
+}
+
+#[derive(Debug, Clone)]
+enum Outcome<T> {
+    Pending,
+    Found(T),
+    NotFound,
+    /// ANAF answered, but listed the company neither as found nor as not found.
+    Missing,
+    Failed(Arc<ApiError>),
+}
+
+/// Coalesces registry lookups per (CUI, date, API version).
+///
+/// Duplicate companies are collapsed, and a company requested while another call is fetching
+/// it is not sent again: the call waits for the other one and reuses its result, or its error.
+/// When the other call is cancelled, the companies it was fetching are claimed again, so the
+/// calls waiting for them still share one upstream request.
+#[derive(Debug)]
+pub struct ItemCoalescer<T> {
+    in_flight: Mutex<HashMap<ItemKey, watch::Receiver<Outcome<T>>>>,
+}
+
+impl<T> Default for ItemCoalescer<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: RegistryItem> ItemCoalescer<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns how many companies are being fetched right now.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+            .lock()
+            .expect("coalescer lock is poisoned")
+            .len()
+    }
+
+    fn claim(&self, version: &str, request: Vec<ApiRequest>) -> (Flight<'_, T>, Vec<Waiter<T>>) {
+        let mut in_flight = self.in_flight.lock().expect("coalescer lock is poisoned");
+        let mut seen = HashSet::new();
+        let mut flight = Flight {
+            coalescer: self,
+            request: vec![],
+            senders: vec![],
+        };
+        let mut waiters = vec![];
+
+        for it in request {
+            let key = ItemKey {
+                registration_code: it.registration_code,
+                when: it.when,
+                version: version.to_owned(),
+            };
+
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+
+            match in_flight.get(&key) {
+                Some(receiver) => waiters.push((it, receiver.clone())),
+                None => {
+                    let (sender, receiver) = watch::channel(Outcome::Pending);
+                    in_flight.insert(key.clone(), receiver);
+                    flight.request.push(it);
+                    flight.senders.push((key, sender));
+                }
+            }
+        }
+
+        (flight, waiters)
+    }
+}
+
+type Waiter<T> = (ApiRequest, watch::Receiver<Outcome<T>>);
+
+/// Companies fetched by one call, released when it completes, fails or is dropped.
+struct Flight<'a, T> {
+    coalescer: &'a ItemCoalescer<T>,
+    request: Vec<ApiRequest>,
+    senders: Vec<(ItemKey, watch::Sender<Outcome<T>>)>,
+}
+
+impl<T: RegistryItem> Flight<'_, T> {
+    fn complete(&self, response: &ApiResponse<T>) {
+        let mut found = HashMap::<Cui, VecDeque<&T>>::new();
+        for item in &response.data {
+            found
+                .entry(item.registration_code())
+                .or_default()
+                .push_back(item);
+        }
+
+        let mut dates = HashMap::<Cui, usize>::new();
+        for (key, _) in &self.senders {
+            *dates.entry(key.registration_code).or_default() += 1;
+        }
+
+        for (key, sender) in &self.senders {
+            // the same company can be asked for on several dates, so items are matched on the
+            // date ANAF echoes back, and only fall back to the request when that is unambiguous
+            let single_date = dates[&key.registration_code] == 1;
+            let item = found.get_mut(&key.registration_code).and_then(|items| {
+                let position = items.iter().position(|item| match item.query_date() {
+                    Some(when) => when == key.when,
+                    None => single_date,
+                })?;
+
+                items.remove(position)
+            });
+
+            let outcome = match item {
+                Some(item) => Outcome::Found(item.clone()),
+                None if response.not_found.contains(&key.registration_code) => Outcome::NotFound,
+                None => Outcome::Missing,
+            };
+
+            sender.send_replace(outcome);
+        }
+    }
+
+    fn fail(&self, error: &ApiError) {
+        let error = Arc::new(error.share());
+
+        for (_, sender) in &self.senders {
+            sender.send_replace(Outcome::Failed(error.clone()));
+        }
+    }
+}
+
+impl<T> Drop for Flight<'_, T> {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .coalescer
+            .in_flight
+            .lock()
+            .expect("coalescer lock is poisoned");
+
+        for (key, _) in &self.senders {
+            in_flight.remove(key);
+        }
+    }
+}
+
+impl<E, T> EndpointCoalescer<E> for ItemCoalescer<T>
+where
+    E: AnafEndpoint<Request = ApiRequest, Response = ApiResponse<T>>,
+    T: RegistryItem,
+{
+    fn send<'a>(
+        &'a self,
+        version: &'a E::Version,
+        request: Vec<ApiRequest>,
+        fetch: Fetch<'a, E>,
+    ) -> FetchFuture<'a, ApiResponse<T>> {
+        Box::pin(async move {
+            let version = version.to_string();
+            let mut response: Option<ApiResponse<T>> = None;
+            let mut request = request;
+
+            while !request.is_empty() {
+                let (flight, waiters) = self.claim(&version, request);
+
+                if !flight.request.is_empty() {
+                    match fetch(flight.request.clone()).await {
+                        Ok(fetched) => {
+                            flight.complete(&fetched);
+                            match &mut response {
+                                Some(response) => response.merge(fetched),
+                                None => response = Some(fetched),
+                            }
+                        }
+                        Err(error) => {
+                            flight.fail(&error);
+                            return Err(error);
+                        }
+                    }
+                }
+                drop(flight);
+
+                if !waiters.is_empty() {
+                    tracing::debug!("Waiting for {} companies in flight", waiters.len());
+                }
+
+                let response = response.get_or_insert_with(empty_response);
+
+                request = vec![];
+                for (it, mut receiver) in waiters {
+                    let outcome = receiver
+                        .wait_for(|outcome| !matches!(outcome, Outcome::Pending))
+                        .await
+                        .map(|outcome| outcome.clone());
+
+                    match outcome {
+                        Ok(Outcome::Found(item)) => response.data.push(item),
+                        Ok(Outcome::NotFound) => response.not_found.push(it.registration_code),
+                        Ok(Outcome::Pending | Outcome::Missing) => {}
+                        Ok(Outcome::Failed(error)) => return Err(error.share()),
+                        // the call fetching the company was cancelled
+                        Err(_) => request.push(it),
+                    }
+                }
+
+                if !request.is_empty() {
+                    tracing::debug!("Fetching {} cancelled companies again", request.len());
+                }
+            }
+
+            Ok(response.unwrap_or_else(empty_response))
+        })
+    }
+}
+
+fn empty_response<T>() -> ApiResponse<T> {
+    ApiResponse {
+        status: 200,
+        message: String::new(),
+        data: vec![],
+        not_found: vec![],
+    }
+}
+
+/// The coalescers shared by all the API handles created from one client.
+#[derive(Debug, Default)]
+pub(crate) struct Coalescers {
+    pub vat_payer: Arc<ItemCoalescer<VatPayerResponseItem>>,
+    #[cfg(feature = "cults_api")]
+    pub cult: Arc<ItemCoalescer<CultResponseItem>>,
+    #[cfg(feature = "farmers_api")]
+    pub farmer: Arc<ItemCoalescer<FarmerResponseItem>>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Mutex, time::Duration};
+
+    use chrono::NaiveDate;
+
+    use crate::{
+        parse_body,
+        vat_payer::{VatPayerEndpoint, VatPayerResponseItem},
+        ApiError, ApiRequest, ApiResponse, Cui, EndpointCoalescer, Fetch,
+    };
+
+    use super::ItemCoalescer;
+
+    fn cuis(bases: &[u64]) -> Vec<Cui> {
+        bases.iter().copied().map(Cui::from_base).collect()
+    }
+
+    fn request(bases: &[u64]) -> Vec<ApiRequest> {
+        let when = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        cuis(bases)
+            .into_iter()
+            .map(|registration_code| ApiRequest::new(registration_code, when))
+            .collect()
+    }
+
+    fn fetch(sent: &Mutex<Vec<Vec<Cui>>>) -> Fetch<'_, VatPayerEndpoint> {
+        Box::new(move |request| {
+            Box::pin(async move {
+                let registration_codes = request
+                    .iter()
+                    .map(|it| it.registration_code)
+                    .collect::<Vec<_>>();
+                sent.lock().unwrap().push(registration_codes.clone());
+                tokio::time::sleep(Duration::from_secs(1)).await;
+
+                Ok(ApiResponse {
+                    status: 200,
+                    message: String::new(),
+                    data: vec![],
+                    not_found: registration_codes,
+                })
+            })
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalescer_shares_companies_in_flight() {
+        let coalescer = ItemCoalescer::<VatPayerResponseItem>::new();
+        let sent = Mutex::new(vec![]);
+        let version = Default::default();
+
+        let (first, second) = tokio::join!(
+            EndpointCoalescer::<VatPayerEndpoint>::send(
+                &coalescer,
+                &version,
+                request(&[1, 2, 2]),
+                fetch(&sent),
+            ),
+            EndpointCoalescer::<VatPayerEndpoint>::send(
+                &coalescer,
+                &version,
+                request(&[2, 3]),
+                fetch(&sent),
+            ),
+        );
+
+        assert_eq!(first.unwrap().not_found, cuis(&[1, 2]));
+        assert_eq!(second.unwrap().not_found, cuis(&[3, 2]));
+
+        assert_eq!(*sent.lock().unwrap(), vec![cuis(&[1, 2]), cuis(&[3])]);
+        assert_eq!(coalescer.in_flight(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalescer_shares_failures() {
+        let coalescer = ItemCoalescer::<VatPayerResponseItem>::new();
+        let sent = Mutex::new(0);
+        let version = Default::default();
+
+        let failing = || -> Fetch<'_, VatPayerEndpoint> {
+            Box::new(|_| {
+                Box::pin(async {
+                    *sent.lock().unwrap() += 1;
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+
+                    Err(ApiError::ServiceUnavailable { retry_after: None })
+                })
+            })
+        };
+
+        let (first, second, third) = tokio::join!(
+            EndpointCoalescer::<VatPayerEndpoint>::send(
+                &coalescer,
+                &version,
+                request(&[1]),
+                failing(),
+            ),
+            EndpointCoalescer::<VatPayerEndpoint>::send(
+                &coalescer,
+                &version,
+                request(&[1]),
+                failing(),
+            ),
+            EndpointCoalescer::<VatPayerEndpoint>::send(
+                &coalescer,
+                &version,
+                request(&[1]),
+                failing(),
+            ),
+        );
+
+        for response in [first, second, third] {
+            assert!(matches!(response, Err(ApiError::ServiceUnavailable { .. })));
+        }
+        assert_eq!(*sent.lock().unwrap(), 1);
+        assert_eq!(coalescer.in_flight(), 0);
+    }
+
+    fn item(registration_code: Cui, when: NaiveDate) -> VatPayerResponseItem {
+        let body = format!(
+            r#"{{
+                "date_generale": {{
+                    "cui": {registration_code}, "data": "{when}", "denumire": "COMPANY SRL",
+                    "stare_inregistrare": "", "statusRO_e_Factura": false
+                }},
+                "inregistrare_scop_Tva": {{"scpTVA": false}},
+                "inregistrare_RTVAI": {{"tipActTvaInc": "", "statusTvaIncasare": false}},
+                "stare_inactiv": {{"statusInactivi": false}},
+                "inregistrare_SplitTVA": {{"statusSplitTVA": false}},
+                "adresa_sediu_social": {{}},
+                "adresa_domiciliu_fiscal": {{}}
+            }}"#
+        );
+
+        parse_body(body.as_bytes()).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalescer_matches_companies_by_date() {
+        let coalescer = ItemCoalescer::<VatPayerResponseItem>::new();
+        let version = Default::default();
+
+        let company = Cui::from_base(1);
+        let january = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let february = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        let request = vec![
+            ApiRequest::new(company, january),
+            ApiRequest::new(company, february),
+        ];
+
+        // ANAF does not have to answer in the order of the request
+        let reversed: Fetch<'_, VatPayerEndpoint> = Box::new(|_| {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+
+                Ok(ApiResponse {
+                    status: 200,
+                    message: String::new(),
+                    data: vec![item(company, february), item(company, january)],
+                    not_found: vec![],
+                })
+            })
+        });
+        let unused: Fetch<'_, VatPayerEndpoint> =
+            Box::new(|_| Box::pin(async { unreachable!("the companies are in flight") }));
+
+        let (_, waiting) = tokio::join!(
+            EndpointCoalescer::<VatPayerEndpoint>::send(
+                &coalescer,
+                &version,
+                request.clone(),
+                reversed
+            ),
+            EndpointCoalescer::<VatPayerEndpoint>::send(&coalescer, &version, request, unused),
+        );
+
+        let dates = waiting
+            .unwrap()
+            .data
+            .into_iter()
+            .map(|it| it.company_info.when)
+            .collect::<Vec<_>>();
+        assert_eq!(dates, vec![Some(january), Some(february)]);
+    }
+}
@@ -0,0 +1,195 @@
+mod builder;
+mod circuit_breaker;
+mod coalescer;
+mod policy;
+mod rate_limiter;
+mod retry;
+
+pub use builder::*;
+pub use circuit_breaker::*;
+pub use coalescer::*;
+pub(crate) use policy::*;
+pub use rate_limiter::*;
+pub use retry::*;
+
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(feature = "vat_payer_async_api")]
+use crate::apis::vat_payer::VatPayerAsyncApi;
+use crate::{
+    apis::vat_payer::{VatPayerApi, VatPayerApiVersion, VatPayerEndpoint},
+    AnafEndpoint, ApiFamily, Caches, EndpointApi, Transport,
+};
+
+#[cfg(feature = "cults_api")]
+use crate::cults::{CultApi, CultApiVersion, CultEndpoint};
+#[cfg(feature = "farmers_api")]
+use crate::farmers::{FarmerApi, FarmerApiVersion, FarmerEndpoint};
+#[cfg(feature = "balance_api")]
+use crate::{
+    balance::{BalanceApi, BalanceApiVersion},
+    BalanceStore,
+};
+
+/// Anaf API Client
+///
+/// Provides access to all Anaf APIs and their versions.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # use anaf_api::{vat_payer::VatPayerApiVersion, AnafClient, ApiRequest};
+/// # async fn run() -> anaf_api::Result<()> {
+/// // Initialize the client
+/// let client = AnafClient::new();
+///
+/// // You'll need to send the current date to the API. You can use chrono for that:
+/// let now = chrono::Local::now().date_naive();
+///
+/// // ANAF has the same request format for VAT Payer, Cult and Farmer APIs.
+/// // However, you can use only one type at a time.
+/// let vat_payer_request = vec![
+///     ApiRequest::new("RO49201783".parse()?, now)
+/// ];
+///
+/// // Send the request to the latest API version.
+/// let response = client.vat_payer(Default::default()).send(vat_payer_request).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Use [`AnafClient::builder`] to change the base URL, timeouts, user agent, proxy, to
+/// share an existing [`reqwest::Client`] or to plug in another [`Transport`].
+///
+/// The client is cheap to clone: all clones share the same connection pool, so a single
+/// instance can be stored in the application state and used from many tasks at once.
+///
+/// Requests are paced to one per second for each [`ApiFamily`] by default, and the limit is
+/// shared by all the API handles created from the client and its clones.
+///
+/// Concurrent lookups of the same company share one upstream request, and duplicate companies
+/// are collapsed before being sent.
+///
+/// Failed requests are not retried unless a [`RetryPolicy`] is set on the builder, ANAF keeps
+/// being called during outages unless a [`CircuitBreakerConfig`] is set on the builder, and the
+/// responses are not cached unless a [`crate::CacheConfig`] or a balance store is set on the
+/// builder.
+#[derive(Debug, Clone)]
+pub struct AnafClient {
+    base_url: Arc<str>,
+    transport: Arc<dyn Transport>,
+    rate_limiters: Arc<HashMap<ApiFamily, RateLimiter>>,
+    circuit_breakers: Arc<HashMap<ApiFamily, CircuitBreaker>>,
+    retry_policy: RetryPolicy,
+    caches: Option<Arc<Caches>>,
+    coalescers: Arc<Coalescers>,
+    #[cfg(feature = "balance_api")]
+    balance_store: Option<Arc<dyn BalanceStore>>,
+}
+
+impl Default for AnafClient {
+    fn default() -> Self {
+        Self::builder()
+            .build()
+            .expect("the default client configuration is valid")
+    }
+}
+
+impl AnafClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn builder() -> AnafClientBuilder {
+        AnafClientBuilder::new()
+    }
+
+    /// Initiates the API of any [`AnafEndpoint`].
+    pub fn endpoint<E: AnafEndpoint>(&self, version: E::Version) -> EndpointApi<E> {
+        EndpointApi::new(
+            version.clone(),
+            self.transport.clone(),
+            &format!("{}{}", self.base_url, E::path(&version)),
+        )
+        .with_rate_limiter(self.rate_limiter(E::FAMILY))
+        .with_circuit_breaker(self.circuit_breaker(E::FAMILY))
+        .with_retry_policy(self.retry_policy)
+    }
+
+    /// Returns the rate limiter shared by all the API handles of the given family.
+    pub fn rate_limiter(&self, family: ApiFamily) -> Option<RateLimiter> {
+        self.rate_limiters.get(&family).cloned()
+    }
+
+    /// Returns the circuit breaker shared by all the API handles of the given family.
+    pub fn circuit_breaker(&self, family: ApiFamily) -> Option<CircuitBreaker> {
+        self.circuit_breakers.get(&family).cloned()
+    }
+
+    /// Returns the state of the circuit breaker of the given family, e.g. to show on a dashboard
+    /// that ANAF is unavailable.
+    pub fn circuit_state(&self, family: ApiFamily) -> Option<CircuitState> {
+        self.circuit_breakers
+            .get(&family)
+            .map(CircuitBreaker::state)
+    }
+}
+
+impl AnafClient {
+    /// Initiates the VatPayer API.
+    pub fn vat_payer(&self, version: VatPayerApiVersion) -> VatPayerApi {
+        self.endpoint::<VatPayerEndpoint>(version)
+            .with_cache(
+                self.caches
+                    .as_ref()
+                    .map(|caches| caches.vat_payer.clone() as _),
+            )
+            .with_coalescer(Some(self.coalescers.vat_payer.clone()))
+    }
+
+    /// Initiates the VatPayer Async API.
+    #[cfg(feature = "vat_payer_async_api")]
+    pub fn async_vat_payer(&self, version: VatPayerApiVersion) -> VatPayerAsyncApi {
+        VatPayerAsyncApi::new(
+            version.clone(),
+            self.transport.clone(),
+            &format!("{}/AsynchWebService/api/{}/ws/tva", self.base_url, version),
+        )
+        .with_rate_limiter(self.rate_limiter(ApiFamily::VatPayerAsync))
+        .with_circuit_breaker(self.circuit_breaker(ApiFamily::VatPayerAsync))
+        .with_retry_policy(self.retry_policy)
+    }
+
+    /// Initiates the Cult API.
+    #[cfg(feature = "cults_api")]
+    pub fn cult(&self, version: CultApiVersion) -> CultApi {
+        self.endpoint::<CultEndpoint>(version)
+            .with_cache(self.caches.as_ref().map(|caches| caches.cult.clone() as _))
+            .with_coalescer(Some(self.coalescers.cult.clone()))
+    }
+
+    /// Initiates the Farmer API.
+    #[cfg(feature = "farmers_api")]
+    pub fn farmer(&self, version: FarmerApiVersion) -> FarmerApi {
+        self.endpoint::<FarmerEndpoint>(version)
+            .with_cache(
+                self.caches
+                    .as_ref()
+                    .map(|caches| caches.farmer.clone() as _),
+            )
+            .with_coalescer(Some(self.coalescers.farmer.clone()))
+    }
+
+    #[cfg(feature = "balance_api")]
+    pub fn balance(&self, version: BalanceApiVersion) -> BalanceApi {
+        BalanceApi::new(
+            version.clone(),
+            self.transport.clone(),
+            &format!("{}/bilant", self.base_url),
+        )
+        .with_rate_limiter(self.rate_limiter(ApiFamily::Balance))
+        .with_circuit_breaker(self.circuit_breaker(ApiFamily::Balance))
+        .with_retry_policy(self.retry_policy)
+        .with_store(self.balance_store.clone())
+    }
+}
@@ -0,0 +1,131 @@
+use std::{fmt::Display, sync::Arc};
+
+use reqwest::StatusCode;
+use tracing::Span;
+
+use crate::{
+    call_span, guard, measure, record_rejected, record_status, tag_request, ApiError, ApiFamily,
+    CircuitBreaker, HttpRequest, HttpResponse, RateLimiter, RawResponse, Result, RetryPolicy,
+    Transport, MAX_REQUEST_SIZE,
+};
+
+/// How an API handle calls ANAF: the transport, and the policies of its endpoint family.
+///
+/// Every API sends its requests through [`CallPolicy::run`] or [`CallPolicy::raw`], so the
+/// rate limit, the circuit breaker, the retries and the instrumentation work the same for all.
+#[derive(Debug, Clone)]
+pub(crate) struct CallPolicy {
+    pub(crate) transport: Arc<dyn Transport>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    pub(crate) retry_policy: RetryPolicy,
+}
+
+impl CallPolicy {
+    pub(crate) fn new(transport: Arc<dyn Transport>) -> Self {
+        Self {
+            transport,
+            rate_limiter: None,
+            circuit_breaker: None,
+            retry_policy: RetryPolicy::none(),
+        }
+    }
+
+    /// Sends the request and reads the response with `read`, retrying as the retry policy says.
+    ///
+    /// Every attempt goes through the circuit breaker and the rate limiter, and runs in its own
+    /// `anaf.request` span.
+    pub(crate) async fn run<T>(
+        &self,
+        family: ApiFamily,
+        version: &impl Display,
+        request: HttpRequest,
+        batch_size: Option<usize>,
+        read: impl Fn(&HttpResponse) -> Result<T>,
+    ) -> Result<T> {
+        let (request, read) = (&request, &read);
+
+        self.retry_policy
+            .retry(|attempt| async move {
+                let result = guard(self.circuit_breaker.as_ref(), async {
+                    self.acquire().await;
+                    let span = span(family, version, request, batch_size, attempt);
+
+                    let call = async {
+                        let mut request = request.clone();
+                        tag_request(&mut request);
+
+                        let response = self.transport.send(request).await?;
+                        record_status(response.status);
+
+                        read(&response)
+                    };
+
+                    measure(family, version, span, call, |_| None).await
+                })
+                .await;
+
+                if let Err(error @ ApiError::CircuitOpen { .. }) = &result {
+                    record_rejected(family, version, error);
+                }
+
+                result
+            })
+            .await
+    }
+
+    /// Sends the request once and returns the response as it is. The retry policy and the
+    /// circuit breaker are skipped, but the rate limit applies.
+    pub(crate) async fn raw<T>(
+        &self,
+        family: ApiFamily,
+        version: &impl Display,
+        request: HttpRequest,
+        batch_size: Option<usize>,
+    ) -> Result<RawResponse<T>> {
+        self.acquire().await;
+        let span = span(family, version, &request, batch_size, 1);
+
+        // the raw response is returned whatever its status, but an error status is still a
+        // failed call
+        let response = RawResponse::fetch(self.transport.as_ref(), request);
+        measure(family, version, span, response, |raw: &RawResponse<T>| {
+            (raw.status() != StatusCode::OK).then(|| ApiError::from_response(&raw.response).name())
+        })
+        .await
+    }
+
+    async fn acquire(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+    }
+}
+
+/// Checks that a batch holds between 1 and [`MAX_REQUEST_SIZE`] companies, counting the invalid
+/// ones as failed calls.
+pub(crate) fn check_batch(family: ApiFamily, version: &impl Display, size: usize) -> Result<()> {
+    if size == 0 || size > MAX_REQUEST_SIZE {
+        let error = ApiError::InvalidRequestError(size);
+        record_rejected(family, version, &error);
+
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+fn span(
+    family: ApiFamily,
+    version: &impl Display,
+    request: &HttpRequest,
+    batch_size: Option<usize>,
+    attempt: u32,
+) -> Span {
+    let span = call_span(family, version, request, attempt);
+    if let Some(batch_size) = batch_size {
+        span.record("anaf.batch_size", batch_size);
+    }
+
+    span
+}
@@ -0,0 +1,150 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// Rate limit settings for a token bucket.
+///
+/// The bucket holds at most `burst` tokens and refills `requests` tokens every `per`.
+/// Every request takes one token, waiting for it when the bucket is empty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    /// ANAF allows about one request per second on the public web services.
+    fn default() -> Self {
+        Self::per_second(1)
+    }
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            requests,
+            per,
+            burst: 1,
+        }
+    }
+
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Allows up to `burst` requests to go out at once after an idle period.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    fn tokens_per_second(&self) -> f64 {
+        self.requests.max(1) as f64 / self.per.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket rate limiter.
+///
+/// Clones share the same bucket, so every API handle created from one [`crate::AnafClient`]
+/// is throttled together, whether the calls come from parallel tasks or a sequential loop.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Waits until a request is allowed to go out.
+    pub async fn acquire(&self) {
+        let wait = self.reserve();
+
+        if !wait.is_zero() {
+            tracing::debug!("Rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token and returns how long the caller has to wait before using it.
+    ///
+    /// The bucket may go below zero: every caller reserves its own slot, so waiting callers are
+    /// served in the order they arrived without holding the lock while sleeping.
+    fn reserve(&self) -> Duration {
+        let rate = self.limit.tokens_per_second();
+        let mut bucket = self.bucket.lock().expect("rate limiter lock is poisoned");
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(self.limit.burst as f64);
+        bucket.refilled_at = now;
+        bucket.tokens -= 1.0;
+
+        match bucket.tokens < 0.0 {
+            true => Duration::from_secs_f64(-bucket.tokens / rate),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{RateLimit, RateLimiter};
+
+    #[tokio::test(start_paused = true)]
+    async fn limiter_paces_sequential_calls() {
+        let limiter = RateLimiter::new(RateLimit::per_second(1));
+        let started_at = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+
+        assert_eq!(started_at.elapsed().as_secs(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limiter_paces_parallel_calls() {
+        let limiter = RateLimiter::new(RateLimit::per_second(2).with_burst(2));
+        let started_at = Instant::now();
+
+        let handles = (0..6)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(started_at.elapsed(), Duration::from_secs(2));
+    }
+}
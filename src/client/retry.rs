@@ -0,0 +1,260 @@
+use std::{
+    collections::hash_map::RandomState,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::{ApiError, Result};
+
+/// Retry policy for failed ANAF calls.
+///
+/// Failed calls are retried with an exponential backoff, randomized by up to half of the delay
+/// so that many workers don't retry at the same time.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # fn main() -> anaf_api::Result<()> {
+/// use std::time::Duration;
+///
+/// use anaf_api::{AnafClient, RetryPolicy};
+///
+/// let client = AnafClient::builder()
+///     .retry_policy(
+///         RetryPolicy::default()
+///             .with_max_attempts(5)
+///             .with_initial_backoff(Duration::from_secs(2)),
+///     )
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    honor_retry_after: bool,
+    is_retryable: fn(&ApiError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            min_backoff: Duration::ZERO,
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+            honor_retry_after: true,
+            is_retryable: is_transient,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Makes every call exactly once.
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// Sets how many times a call is made in total, including the first attempt.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the lower bound of the delay between retries, which also applies to the
+    /// `Retry-After` delay.
+    pub fn with_min_backoff(mut self, backoff: Duration) -> Self {
+        self.min_backoff = backoff;
+        self
+    }
+
+    /// Sets the upper bound of the delay between retries, which also applies to the
+    /// `Retry-After` delay.
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the factor the delay grows by after every retry.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Waits for the `Retry-After` delay sent by ANAF instead of the computed backoff, up to the
+    /// maximum backoff.
+    pub fn with_retry_after(mut self, honor_retry_after: bool) -> Self {
+        self.honor_retry_after = honor_retry_after;
+        self
+    }
+
+    /// Sets which errors are retried. By default, see [`is_transient`].
+    pub fn retry_if(mut self, is_retryable: fn(&ApiError) -> bool) -> Self {
+        self.is_retryable = is_retryable;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Calls `call` until it succeeds, fails with an error which is not retryable or runs out of
+    /// attempts. `call` receives the attempt number, starting with 1.
+    pub async fn retry<T, F, Fut>(&self, call: F) -> Result<T>
+    where
+        F: Fn(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match call(attempt).await {
+                Err(error) if attempt < self.max_attempts && (self.is_retryable)(&error) => {
+                    let delay = self.delay(attempt, &error);
+                    tracing::warn!(
+                        "ANAF call failed on attempt {attempt}/{max_attempts}, retrying in {delay:?}: {error}",
+                        max_attempts = self.max_attempts
+                    );
+
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn delay(&self, attempt: u32, error: &ApiError) -> Duration {
+        self.backoff(attempt, error).max(self.min_backoff)
+    }
+
+    fn backoff(&self, attempt: u32, error: &ApiError) -> Duration {
+        if self.honor_retry_after {
+            if let Some(retry_after) = error.retry_after() {
+                return retry_after.min(self.max_backoff);
+            }
+        }
+
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32 - 1);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+
+        match self.jitter {
+            true => Duration::from_secs_f64(backoff * (0.5 + random_fraction() / 2.0)),
+            false => Duration::from_secs_f64(backoff),
+        }
+    }
+}
+
+/// Retries the errors which are [`ApiError::is_retryable`], such as maintenance windows,
+/// throttling, timeouts and connection failures.
+pub fn is_transient(error: &ApiError) -> bool {
+    error.is_retryable()
+}
+
+/// Reads the `Retry-After` header, given either in seconds or as an HTTP date.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+/// Returns a random number between 0 and 1, good enough to spread retries.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    use crate::ApiError;
+
+    use super::{retry_after, RetryPolicy};
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        let policy = RetryPolicy::default()
+            .with_jitter(false)
+            .with_max_backoff(Duration::from_secs(5));
+        let error = ApiError::ServiceUnavailable { retry_after: None };
+
+        assert_eq!(policy.delay(1, &error), Duration::from_secs(1));
+        assert_eq!(policy.delay(2, &error), Duration::from_secs(2));
+        assert_eq!(policy.delay(3, &error), Duration::from_secs(4));
+        assert_eq!(policy.delay(4, &error), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn backoff_honors_retry_after() {
+        let policy = RetryPolicy::default();
+        let error = ApiError::ServiceUnavailable {
+            retry_after: Some(Duration::from_secs(7)),
+        };
+
+        assert_eq!(policy.delay(1, &error), Duration::from_secs(7));
+
+        // a single header must not park the call for longer than the policy allows
+        let policy = policy.with_max_backoff(Duration::from_secs(60));
+        let error = ApiError::ServiceUnavailable {
+            retry_after: Some(Duration::from_secs(2 * 60 * 60)),
+        };
+
+        assert_eq!(policy.delay(1, &error), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn retry_after_is_parsed_from_seconds() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn policy_stops_on_permanent_errors() {
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        let result: crate::Result<()> = RetryPolicy::default()
+            .retry(|_| async {
+                attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Err(ApiError::NotFound {
+                    body: String::new(),
+                })
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.into_inner(), 1);
+    }
+}
@@ -0,0 +1,40 @@
+use std::future::Future;
+
+use crate::{ApiError, ApiRequest, ApiResponse, Result, MAX_REQUEST_SIZE};
+
+/// Splits the request into chunks ANAF accepts, sends them one after another and merges the
+/// responses.
+pub(crate) async fn send_in_batches<T, F, Fut>(
+    request: Vec<ApiRequest>,
+    send: F,
+) -> Result<ApiResponse<T>>
+where
+    F: Fn(Vec<ApiRequest>) -> Fut,
+    Fut: Future<Output = Result<ApiResponse<T>>>,
+{
+    if request.is_empty() {
+        return Err(ApiError::InvalidRequestError(0));
+    }
+
+    let mut merged: Option<ApiResponse<T>> = None;
+
+    for (chunk, request) in request.chunks(MAX_REQUEST_SIZE).enumerate() {
+        tracing::debug!("Sending batch {} with {} companies", chunk, request.len());
+
+        let response = send(request.to_vec())
+            .await
+            .map_err(|source| ApiError::BatchError {
+                chunk,
+                registration_codes: request.iter().map(|it| it.registration_code).collect(),
+                source: Box::new(source),
+            })?;
+
+        match merged.as_mut() {
+            Some(merged) => merged.merge(response),
+            None => merged = Some(response),
+        }
+    }
+
+    // the request is not empty, so at least one chunk was sent
+    Ok(merged.expect("at least one batch is sent"))
+}
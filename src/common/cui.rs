@@ -0,0 +1,241 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// Weights of the CUI control digit, applied to the digits before it, right-aligned.
+const CONTROL_KEY: [u64; 9] = [7, 5, 3, 2, 1, 7, 5, 3, 2];
+
+/// Romanian fiscal identification code (CUI / CIF).
+///
+/// Parsing accepts an optional `RO` prefix in any case, with or without a space after it, and
+/// surrounding whitespace. The last digit is checked against the control digit computed from
+/// the others, so typos are caught before anything is sent to ANAF.
+///
+/// It shows bare by default, and with the `RO` prefix in the alternate form:
+///
+/// ```rust
+/// # fn main() -> anaf_api::Result<()> {
+/// use anaf_api::Cui;
+///
+/// let cui: Cui = "ro 49201783".parse()?;
+///
+/// assert_eq!(cui.to_string(), "49201783");
+/// assert_eq!(format!("{cui:#}"), "RO49201783");
+/// assert!("RO49201784".parse::<Cui>().is_err());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// >>**Note**: the CUIs in ANAF responses are taken as they are, without checking them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cui(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CuiError {
+    #[error("CUI `{0}` must hold between 2 and 10 digits")]
+    Length(String),
+
+    #[error("CUI `{0}` may only hold digits after the RO prefix")]
+    NotNumeric(String),
+
+    #[error("CUI `{input}` ends in {found}, but its control digit is {expected}")]
+    ControlDigit {
+        input: String,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl Cui {
+    /// Checks the control digit of a CUI given as a number.
+    pub fn new(value: u64) -> Result<Self, CuiError> {
+        Self::check(value, &value.to_string())
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns the CUI with the `RO` prefix used by VAT payers, e.g. `RO49201783`.
+    pub fn prefixed(&self) -> String {
+        format!("{self:#}")
+    }
+
+    /// Builds a valid CUI by appending the control digit to the given digits.
+    #[cfg(test)]
+    pub(crate) fn from_base(base: u64) -> Self {
+        Self(base * 10 + control_digit(base))
+    }
+
+    fn check(value: u64, input: &str) -> Result<Self, CuiError> {
+        if !(10..=9_999_999_999).contains(&value) {
+            return Err(CuiError::Length(input.to_owned()));
+        }
+
+        let expected = control_digit(value / 10);
+        let found = value % 10;
+
+        match expected == found {
+            true => Ok(Self(value)),
+            false => Err(CuiError::ControlDigit {
+                input: input.to_owned(),
+                expected,
+                found,
+            }),
+        }
+    }
+}
+
+/// Computes the control digit of the digits before it.
+fn control_digit(base: u64) -> u64 {
+    let mut base = base;
+    let mut sum = 0;
+
+    for weight in CONTROL_KEY.iter().rev() {
+        sum += base % 10 * weight;
+        base /= 10;
+    }
+
+    sum * 10 % 11 % 10
+}
+
+/// Trims the input and drops the `RO` prefix, if any.
+fn strip_prefix(input: &str) -> &str {
+    let trimmed = input.trim();
+
+    match trimmed.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("RO") => trimmed[2..].trim_start(),
+        _ => trimmed,
+    }
+}
+
+impl FromStr for Cui {
+    type Err = CuiError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let digits = strip_prefix(input);
+
+        if digits.is_empty() || !digits.bytes().all(|it| it.is_ascii_digit()) {
+            return Err(CuiError::NotNumeric(input.to_owned()));
+        }
+
+        let value = match digits.trim_start_matches('0').len() {
+            0..=10 => digits.parse().unwrap_or_default(),
+            _ => return Err(CuiError::Length(input.to_owned())),
+        };
+
+        Self::check(value, input)
+    }
+}
+
+impl TryFrom<u64> for Cui {
+    type Error = CuiError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<Cui> for u64 {
+    fn from(value: Cui) -> Self {
+        value.0
+    }
+}
+
+impl PartialEq<u64> for Cui {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Display for Cui {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match f.alternate() {
+            true => write!(f, "RO{}", self.0),
+            false => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl Serialize for Cui {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cui {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = Cui;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a CUI, as a number or a string")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Cui, E> {
+                Ok(Cui(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Cui, E> {
+                u64::try_from(value)
+                    .map(Cui)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Cui, E> {
+                strip_prefix(value)
+                    .parse()
+                    .map(Cui)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cui, CuiError};
+
+    #[test]
+    fn cui_parses_prefixed_and_bare_forms() {
+        for input in [
+            "RO49201783",
+            " 49201783 ",
+            "ro 49201783",
+            "Ro49201783",
+            "0049201783",
+        ] {
+            assert_eq!(input.parse::<Cui>(), Ok(Cui(49201783)), "{input}");
+        }
+
+        assert_eq!(Cui::new(40914732).unwrap().prefixed(), "RO40914732");
+        assert_eq!(Cui::from_base(4920178), Cui(49201783));
+    }
+
+    #[test]
+    fn cui_rejects_invalid_codes() {
+        assert!(matches!(
+            "RO49201784".parse::<Cui>(),
+            Err(CuiError::ControlDigit {
+                expected: 3,
+                found: 4,
+                ..
+            })
+        ));
+        assert!(matches!("RO".parse::<Cui>(), Err(CuiError::NotNumeric(_))));
+        assert!(matches!(
+            "4920-1783".parse::<Cui>(),
+            Err(CuiError::NotNumeric(_))
+        ));
+        assert!(matches!("7".parse::<Cui>(), Err(CuiError::Length(_))));
+        assert!(matches!(
+            "123456789012".parse::<Cui>(),
+            Err(CuiError::Length(_))
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::NaiveDate;
+use serde::{de, Deserialize, Deserializer};
+
+/// Format of the dates sent by ANAF.
+pub const ANAF_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Deserializes an optional ANAF date, given as `YYYY-MM-DD`. Empty strings and `null`, which
+/// ANAF sends for "no date", become `None`, and malformed dates are rejected.
+pub(crate) fn deserialize_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    match value.trim() {
+        "" => Ok(None),
+        date => NaiveDate::parse_from_str(date, ANAF_DATE_FORMAT)
+            .map(Some)
+            .map_err(|error| {
+                de::Error::custom(format!(
+                    "invalid date `{date}`, expected YYYY-MM-DD: {error}"
+                ))
+            }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use serde::Deserialize;
+
+    use crate::{parse_body, ApiError};
+
+    #[derive(Debug, Deserialize)]
+    struct Dated {
+        #[serde(default, deserialize_with = "super::deserialize_date")]
+        at: Option<NaiveDate>,
+    }
+
+    #[test]
+    fn date_accepts_empty_values() {
+        for body in [r#"{"at":""}"#, r#"{"at":" "}"#, r#"{"at":null}"#, "{}"] {
+            assert!(parse_body::<Dated>(body.as_bytes()).unwrap().at.is_none());
+        }
+
+        let dated = parse_body::<Dated>(br#"{"at":"2024-02-29"}"#).unwrap();
+        assert_eq!(dated.at, NaiveDate::from_ymd_opt(2024, 2, 29));
+    }
+
+    #[test]
+    fn date_rejects_malformed_values() {
+        match parse_body::<Dated>(br#"{"at":"29.02.2024"}"#) {
+            Err(ApiError::MalformedResponse { path, message, .. }) => {
+                assert_eq!(path, "at");
+                assert!(message.contains("29.02.2024"));
+            }
+            other => panic!("expected a malformed response, got {other:?}"),
+        }
+    }
+}
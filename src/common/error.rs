@@ -0,0 +1,394 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::{retry_after, ApiFamily, Cui, CuiError, HttpResponse, MAX_REQUEST_SIZE};
+
+/// The longest part of a response body kept in an error.
+pub const MAX_BODY_SNIPPET: usize = 512;
+
+pub type Result<T> = std::result::Result<T, ApiError>;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("ANAF API is under maintenance")]
+    ServiceUnavailable { retry_after: Option<Duration> },
+
+    #[error("ANAF API is throttling the requests")]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("ANAF API endpoint was not found: {body}")]
+    NotFound { body: String },
+
+    #[error("ANAF API rejected the request with {status}: {body}")]
+    Rejected {
+        status: StatusCode,
+        kind: AnafErrorKind,
+        body: String,
+    },
+
+    #[error("ANAF API answered with code {code}: {message}")]
+    Refused {
+        code: usize,
+        kind: AnafErrorKind,
+        message: String,
+        not_found: Vec<Cui>,
+    },
+
+    #[error("ANAF API returned a malformed response at `{path}`: {message}")]
+    MalformedResponse {
+        path: String,
+        message: String,
+        body: String,
+    },
+
+    #[error("ANAF {family} API is considered unavailable, its circuit breaker is open")]
+    CircuitOpen {
+        family: ApiFamily,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("ANAF API did not answer in time")]
+    Timeout,
+
+    #[error(
+        "ANAF API supports fetching between 1 and {} companies, got {0}.",
+        MAX_REQUEST_SIZE
+    )]
+    InvalidRequestError(usize),
+
+    #[error(transparent)]
+    InvalidCui(#[from] CuiError),
+
+    #[error("Batch {chunk} ({registration_codes:?}) failed: {source}")]
+    BatchError {
+        chunk: usize,
+        registration_codes: Vec<Cui>,
+        source: Box<ApiError>,
+    },
+
+    #[error("ANAF async API did not return the result for {0} before the deadline")]
+    AsyncDeadlineExceeded(String),
+
+    #[error("No interaction recorded in {cassette} matches {method} {url}")]
+    UnrecordedRequest {
+        cassette: String,
+        method: reqwest::Method,
+        url: String,
+    },
+
+    #[error("Transport error: {0}")]
+    TransportError(String),
+
+    #[error("Reqwest error: {0}")]
+    ReqwestError(reqwest::Error),
+
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Query String error: {0}")]
+    QueryStringError(#[from] serde_qs::Error),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.is_timeout() {
+            true => Self::Timeout,
+            false => Self::ReqwestError(error),
+        }
+    }
+}
+
+impl ApiError {
+    /// Maps a response which ANAF did not answer with HTTP 200.
+    pub(crate) fn from_response(response: &HttpResponse) -> Self {
+        match response.status {
+            StatusCode::SERVICE_UNAVAILABLE => Self::ServiceUnavailable {
+                retry_after: retry_after(&response.headers),
+            },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited {
+                retry_after: retry_after(&response.headers),
+            },
+            StatusCode::NOT_FOUND => Self::NotFound {
+                body: snippet(&response.body),
+            },
+            status => Self::Rejected {
+                status,
+                kind: AnafErrorKind::from_body(&response.body),
+                body: snippet(&response.body),
+            },
+        }
+    }
+
+    /// Returns how long ANAF asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::ServiceUnavailable { retry_after }
+            | Self::RateLimited { retry_after }
+            | Self::CircuitOpen { retry_after, .. } => *retry_after,
+            Self::BatchError { source, .. } => source.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status ANAF answered with, if the request got that far.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::ServiceUnavailable { .. } => Some(StatusCode::SERVICE_UNAVAILABLE),
+            Self::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            Self::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Self::Rejected { status, .. } => Some(*status),
+            Self::Refused { .. } => Some(StatusCode::OK),
+            Self::BatchError { source, .. } => source.status(),
+            Self::ReqwestError(error) => error.status(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the same request may succeed later, e.g. after a maintenance window,
+    /// throttling, a timeout or a connection failure.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::ServiceUnavailable { .. } | Self::RateLimited { .. } | Self::Timeout => true,
+            Self::Rejected { status, kind, .. } => {
+                *kind == AnafErrorKind::Throttled
+                    || matches!(
+                        *status,
+                        StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT
+                    )
+            }
+            Self::Refused { code, kind, .. } => {
+                *kind == AnafErrorKind::Throttled || matches!(*code, 429 | 503)
+            }
+            Self::BatchError { source, .. } => source.is_retryable(),
+            Self::ReqwestError(error) => error.is_connect() || error.is_request(),
+            _ => false,
+        }
+    }
+}
+
+impl ApiError {
+    /// Copies the error for the other calls waiting on the same request. The errors which
+    /// cannot be cloned keep their message only.
+    pub(crate) fn share(&self) -> Self {
+        match self {
+            Self::ServiceUnavailable { retry_after } => Self::ServiceUnavailable {
+                retry_after: *retry_after,
+            },
+            Self::RateLimited { retry_after } => Self::RateLimited {
+                retry_after: *retry_after,
+            },
+            Self::NotFound { body } => Self::NotFound { body: body.clone() },
+            Self::Rejected { status, kind, body } => Self::Rejected {
+                status: *status,
+                kind: *kind,
+                body: body.clone(),
+            },
+            Self::Refused {
+                code,
+                kind,
+                message,
+                not_found,
+            } => Self::Refused {
+                code: *code,
+                kind: *kind,
+                message: message.clone(),
+                not_found: not_found.clone(),
+            },
+            Self::MalformedResponse {
+                path,
+                message,
+                body,
+            } => Self::MalformedResponse {
+                path: path.clone(),
+                message: message.clone(),
+                body: body.clone(),
+            },
+            Self::CircuitOpen {
+                family,
+                retry_after,
+            } => Self::CircuitOpen {
+                family: *family,
+                retry_after: *retry_after,
+            },
+            Self::Timeout => Self::Timeout,
+            Self::InvalidRequestError(size) => Self::InvalidRequestError(*size),
+            Self::InvalidCui(error) => Self::InvalidCui(error.clone()),
+            Self::BatchError {
+                chunk,
+                registration_codes,
+                source,
+            } => Self::BatchError {
+                chunk: *chunk,
+                registration_codes: registration_codes.clone(),
+                source: Box::new(source.share()),
+            },
+            Self::AsyncDeadlineExceeded(token) => Self::AsyncDeadlineExceeded(token.clone()),
+            Self::UnrecordedRequest {
+                cassette,
+                method,
+                url,
+            } => Self::UnrecordedRequest {
+                cassette: cassette.clone(),
+                method: method.clone(),
+                url: url.clone(),
+            },
+            Self::TransportError(message) => Self::TransportError(message.clone()),
+            Self::ReqwestError(_)
+            | Self::JsonError(_)
+            | Self::QueryStringError(_)
+            | Self::IoError(_) => Self::TransportError(self.to_string()),
+        }
+    }
+
+    /// Returns the name of the variant, e.g. to label metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ServiceUnavailable { .. } => "service_unavailable",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::NotFound { .. } => "not_found",
+            Self::Rejected { .. } => "rejected",
+            Self::Refused { .. } => "refused",
+            Self::MalformedResponse { .. } => "malformed_response",
+            Self::CircuitOpen { .. } => "circuit_open",
+            Self::Timeout => "timeout",
+            Self::InvalidRequestError(_) => "invalid_request",
+            Self::InvalidCui(_) => "invalid_cui",
+            Self::BatchError { .. } => "batch",
+            Self::AsyncDeadlineExceeded(_) => "async_deadline_exceeded",
+            Self::UnrecordedRequest { .. } => "unrecorded_request",
+            Self::TransportError(_) => "transport",
+            Self::ReqwestError(_) => "reqwest",
+            Self::JsonError(_) => "json",
+            Self::QueryStringError(_) => "query_string",
+            Self::IoError(_) => "io",
+        }
+    }
+}
+
+/// Reason given by ANAF for rejecting a request, recognized from its Romanian message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnafErrorKind {
+    /// The request holds more companies than ANAF accepts.
+    TooManyCompanies,
+    /// A date of the request is malformed or in the future.
+    InvalidDate,
+    /// A CUI of the request is malformed.
+    InvalidRegistrationCode,
+    /// ANAF asks for fewer requests.
+    Throttled,
+    /// ANAF has no data for the request.
+    NoData,
+    /// The message was not recognized.
+    Other,
+}
+
+impl AnafErrorKind {
+    /// Recognizes the message of an error body, given either as JSON or as plain text.
+    pub fn from_body(body: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(body);
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|body| body.get("message")?.as_str().map(str::to_owned))
+            .unwrap_or_else(|| text.into_owned());
+
+        Self::from_message(&message)
+    }
+
+    /// Recognizes an ANAF error message, with or without diacritics.
+    pub fn from_message(message: &str) -> Self {
+        let message = normalize(message);
+        let has = |pattern: &str| message.contains(pattern);
+
+        if has("maxim") && (has("cui") || has("500")) || has("mai mult de 500") {
+            Self::TooManyCompanies
+        } else if has("data") && (has("invalid") || has("viitor") || has("format")) {
+            Self::InvalidDate
+        } else if (has("cui") || has("cod fiscal") || has("cif")) && has("invalid") {
+            Self::InvalidRegistrationCode
+        } else if has("prea multe") || has("limita") {
+            Self::Throttled
+        } else if has("nu exista") || has("nu a fost gasit") || has("nu au fost gasite") {
+            Self::NoData
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Lowercases the message and strips the Romanian diacritics.
+pub(crate) fn normalize(message: &str) -> String {
+    message
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ă' | 'â' => 'a',
+            'î' => 'i',
+            'ș' | 'ş' => 's',
+            'ț' | 'ţ' => 't',
+            c => c,
+        })
+        .collect()
+}
+
+/// Keeps the start of a response body, for error messages and logs.
+pub(crate) fn snippet(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+
+    match text.char_indices().nth(MAX_BODY_SNIPPET) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::StatusCode;
+
+    use crate::HttpResponse;
+
+    use super::{AnafErrorKind, ApiError, MAX_BODY_SNIPPET};
+
+    #[test]
+    fn error_recognizes_romanian_messages() {
+        assert_eq!(
+            AnafErrorKind::from_message("Numărul maxim de CUI-uri este 500"),
+            AnafErrorKind::TooManyCompanies
+        );
+        assert_eq!(
+            AnafErrorKind::from_body(br#"{"cod":400,"message":"Data nu poate fi in viitor"}"#),
+            AnafErrorKind::InvalidDate
+        );
+        assert_eq!(
+            AnafErrorKind::from_message("CUI invalid"),
+            AnafErrorKind::InvalidRegistrationCode
+        );
+        assert_eq!(
+            AnafErrorKind::from_message("Internal error"),
+            AnafErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn error_maps_statuses() {
+        let error = ApiError::from_response(&HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, ""));
+        assert!(matches!(error, ApiError::RateLimited { .. }));
+        assert!(error.is_retryable());
+
+        let body = "x".repeat(2 * MAX_BODY_SNIPPET);
+        let error = ApiError::from_response(&HttpResponse::new(StatusCode::BAD_REQUEST, body));
+        assert_eq!(error.status(), Some(StatusCode::BAD_REQUEST));
+        assert!(!error.is_retryable());
+
+        match error {
+            ApiError::Rejected { body, .. } => {
+                assert_eq!(body.chars().count(), MAX_BODY_SNIPPET + 1)
+            }
+            error => panic!("unexpected error: {error}"),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+/// The ANAF endpoint families exposed by the client.
+///
+/// Client-wide policies, such as rate limiting, are configured per family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ApiFamily {
+    VatPayer,
+    VatPayerAsync,
+    Balance,
+    Cult,
+    Farmer,
+}
+
+impl ApiFamily {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::VatPayer,
+            Self::VatPayerAsync,
+            Self::Balance,
+            Self::Cult,
+            Self::Farmer,
+        ]
+    }
+}
+
+impl Display for ApiFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::VatPayer => "vat_payer",
+                Self::VatPayerAsync => "vat_payer_async",
+                Self::Balance => "balance",
+                Self::Cult => "cult",
+                Self::Farmer => "farmer",
+            }
+        )
+    }
+}
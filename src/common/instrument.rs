@@ -0,0 +1,464 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use chrono::Utc;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    StatusCode,
+};
+use tracing::{
+    field::{display, Empty},
+    Instrument, Span,
+};
+
+use crate::{ApiError, ApiFamily, HttpRequest, Result};
+
+/// Target of the events dumping whole requests and responses, at the `TRACE` level.
+///
+/// They are left out unless enabled explicitly, e.g. with `RUST_LOG=anaf_api_payload=trace`.
+pub const PAYLOAD_TARGET: &str = "anaf_api_payload";
+
+/// Header carrying the id of a call, which is also recorded on its span as `anaf.request_id`.
+///
+/// ANAF does not send an id of its own, so every call is given one, which can be quoted when
+/// matching a call with the logs or when reporting it.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Counter of the calls made to ANAF, labeled by `api`, `version`, `outcome` and `error`.
+///
+/// Calls stopped before reaching ANAF, by an open circuit breaker or an invalid batch size, are
+/// counted as errors as well. CUIs are checked when they are parsed, before any call is made, so
+/// `invalid_cui` errors are not counted.
+pub const REQUESTS_TOTAL: &str = "anaf_api_requests_total";
+
+/// Histogram of the call durations in seconds, labeled by `api`, `version` and `outcome`.
+pub const REQUEST_DURATION_SECONDS: &str = "anaf_api_request_duration_seconds";
+
+/// Gauge of the calls waiting for ANAF, labeled by `api`.
+pub const REQUESTS_IN_FLIGHT: &str = "anaf_api_requests_in_flight";
+
+/// Counter of the CUIs ANAF did not find, labeled by `api` and `version`.
+pub const NOT_FOUND_TOTAL: &str = "anaf_api_not_found_total";
+
+/// Gauge set to 1 while the circuit breaker of an API is open, labeled by `api`.
+pub const CIRCUIT_OPEN: &str = "anaf_api_circuit_open";
+
+/// Creates the span of one call to ANAF, following the OpenTelemetry conventions for HTTP
+/// clients. The fields left empty are recorded once the call completes.
+pub(crate) fn call_span(
+    family: ApiFamily,
+    version: &impl Display,
+    request: &HttpRequest,
+    attempt: u32,
+) -> Span {
+    tracing::info_span!(
+        "anaf.request",
+        otel.name = %format_args!("{} {}", request.method, family),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        anaf.api = %family,
+        anaf.version = %version,
+        anaf.batch_size = Empty,
+        anaf.attempt = attempt,
+        anaf.request_id = Empty,
+        anaf.found = Empty,
+        anaf.not_found = Empty,
+        http.request.method = %request.method,
+        http.response.status_code = Empty,
+        url.full = %request.url,
+        duration_ms = Empty,
+        "error.type" = Empty,
+    )
+}
+
+/// Gives the request a new id, sent in the [`REQUEST_ID_HEADER`] header and recorded on the span
+/// of the current call. Ids are unique within the process, and start with the time of the call.
+pub(crate) fn tag_request(request: &mut HttpRequest) -> String {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    let id = format!(
+        "{:x}-{:x}-{:x}",
+        Utc::now().timestamp_millis(),
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    request.headers.insert(
+        HeaderName::from_static(REQUEST_ID_HEADER),
+        HeaderValue::from_str(&id).expect("request ids are ASCII"),
+    );
+    Span::current().record("anaf.request_id", display(&id));
+
+    id
+}
+
+/// Records the HTTP status on the span of the current call.
+pub(crate) fn record_status(status: StatusCode) {
+    Span::current().record("http.response.status_code", status.as_u16());
+}
+
+/// Runs one call to ANAF in its span, recording its outcome and duration on the span and, with
+/// the `metrics` feature, as metrics.
+///
+/// `failure` names the error of a call which returned, but failed anyway, such as a raw call
+/// answered with an error status.
+pub(crate) async fn measure<T>(
+    family: ApiFamily,
+    version: &impl Display,
+    span: Span,
+    call: impl Future<Output = Result<T>>,
+    failure: impl FnOnce(&T) -> Option<&'static str>,
+) -> Result<T> {
+    #[cfg(feature = "metrics")]
+    let _in_flight = InFlight::start(&family.to_string());
+    let started_at = Instant::now();
+
+    let result = call.instrument(span.clone()).await;
+
+    let (outcome, error) = match &result {
+        Ok(value) => match failure(value) {
+            Some(error) => ("error", error),
+            None => ("success", "none"),
+        },
+        Err(error) => ("error", error.name()),
+    };
+
+    span.record("duration_ms", started_at.elapsed().as_millis() as u64);
+    match outcome {
+        "success" => span.record("otel.status_code", "OK"),
+        _ => span
+            .record("otel.status_code", "ERROR")
+            .record("error.type", error),
+    };
+
+    #[cfg(feature = "metrics")]
+    {
+        let labels = [
+            ("api", family.to_string()),
+            ("version", version.to_string()),
+            ("outcome", outcome.to_owned()),
+            ("error", error.to_owned()),
+        ];
+
+        ::metrics::counter!(REQUESTS_TOTAL, &labels).increment(1);
+        ::metrics::histogram!(REQUEST_DURATION_SECONDS, &labels[..3])
+            .record(started_at.elapsed().as_secs_f64());
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = (family, version, outcome);
+
+    result
+}
+
+/// Counts a call which failed before reaching ANAF, with the `metrics` feature.
+pub(crate) fn record_rejected(family: ApiFamily, version: &impl Display, error: &ApiError) {
+    Span::current().record("error.type", error.name());
+
+    #[cfg(feature = "metrics")]
+    {
+        let labels = [
+            ("api", family.to_string()),
+            ("version", version.to_string()),
+            ("outcome", "error".to_owned()),
+            ("error", error.name().to_owned()),
+        ];
+        ::metrics::counter!(REQUESTS_TOTAL, &labels).increment(1);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = (family, version);
+}
+
+/// Records how many CUIs ANAF found and did not find on the span of the current call and, with
+/// the `metrics` feature, counts the ones not found.
+pub(crate) fn record_found(
+    family: ApiFamily,
+    version: &impl Display,
+    found: usize,
+    not_found: usize,
+) {
+    Span::current()
+        .record("anaf.found", found)
+        .record("anaf.not_found", not_found);
+
+    #[cfg(feature = "metrics")]
+    if not_found > 0 {
+        let labels = [
+            ("api", family.to_string()),
+            ("version", version.to_string()),
+        ];
+        ::metrics::counter!(NOT_FOUND_TOTAL, &labels).increment(not_found as u64);
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = (family, version);
+}
+
+/// Records whether the circuit breaker of an API is open, with the `metrics` feature.
+pub(crate) fn record_circuit_state(family: ApiFamily, open: bool) {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!(CIRCUIT_OPEN, "api" => family.to_string()).set(if open { 1.0 } else { 0.0 });
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = (family, open);
+}
+
+/// Keeps the in-flight gauge up, until the call completes or is cancelled.
+#[cfg(feature = "metrics")]
+struct InFlight(::metrics::Gauge);
+
+#[cfg(feature = "metrics")]
+impl InFlight {
+    fn start(api: &str) -> Self {
+        let gauge = ::metrics::gauge!(REQUESTS_IN_FLIGHT, "api" => api.to_owned());
+        gauge.increment(1.0);
+        Self(gauge)
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.decrement(1.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::Utc;
+    use reqwest::Method;
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    use crate::{AnafClient, ApiRequest, Cui, HttpResponse, InMemoryTransport};
+
+    use super::REQUEST_ID_HEADER;
+
+    type Fields = Arc<Mutex<HashMap<String, String>>>;
+
+    /// Collects the fields of the `anaf.request` spans.
+    struct SpanFields(Fields);
+
+    impl Visit for SpanFields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_owned(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanFields {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            if attrs.metadata().name() == "anaf.request" {
+                attrs.record(&mut SpanFields(self.0.clone()));
+            }
+        }
+
+        fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            values.record(&mut SpanFields(self.0.clone()));
+        }
+    }
+
+    #[tokio::test]
+    async fn call_is_recorded_in_a_span() {
+        let fields = Fields::default();
+        let _guard = tracing_subscriber::registry()
+            .with(SpanFields(fields.clone()))
+            .set_default();
+
+        let transport = InMemoryTransport::new();
+        transport.respond(
+            Method::POST,
+            "/PlatitorTvaRest/api/v8/ws/tva",
+            HttpResponse::json(r#"{"cod":200,"message":"","found":[],"notFound":[111111115]}"#),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport)
+            .without_rate_limit()
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        client
+            .vat_payer(Default::default())
+            .send(request)
+            .await
+            .unwrap();
+
+        let fields = fields.lock().unwrap();
+        assert_eq!(fields["anaf.api"], "vat_payer");
+        assert_eq!(fields["anaf.version"], "v8");
+        assert_eq!(fields["anaf.batch_size"], "1");
+        assert_eq!(fields["anaf.attempt"], "1");
+        assert_eq!(fields["anaf.not_found"], "1");
+        assert_eq!(fields["http.response.status_code"], "200");
+        assert_eq!(fields["otel.status_code"], "\"OK\"");
+    }
+
+    #[tokio::test]
+    async fn raw_call_is_tagged_with_an_id() {
+        let fields = Fields::default();
+        let _guard = tracing_subscriber::registry()
+            .with(SpanFields(fields.clone()))
+            .set_default();
+
+        let transport = InMemoryTransport::new();
+        for _ in 0..2 {
+            transport.respond(
+                Method::POST,
+                "/PlatitorTvaRest/api/v8/ws/tva",
+                HttpResponse::json(r#"{"cod":200,"message":"","found":[],"notFound":[19]}"#),
+            );
+        }
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .build()
+            .unwrap();
+        let api = client.vat_payer(Default::default());
+
+        let request = vec![ApiRequest::new(Cui::from_base(1), Utc::now().date_naive())];
+        let first = api.send_raw(request.clone()).await.unwrap();
+        assert_eq!(
+            fields.lock().unwrap()["anaf.request_id"],
+            first.request_id()
+        );
+
+        let second = api.send_raw(request).await.unwrap();
+        assert_ne!(first.request_id(), second.request_id());
+
+        let sent = transport
+            .requests()
+            .iter()
+            .map(|request| {
+                request.headers[REQUEST_ID_HEADER]
+                    .to_str()
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sent, vec![first.request_id(), second.request_id()]);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn calls_are_counted() {
+        use std::time::Duration;
+
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+        use reqwest::StatusCode;
+
+        use crate::{ApiError, CircuitBreakerConfig, RetryPolicy};
+
+        use super::{NOT_FOUND_TOTAL, REQUESTS_TOTAL};
+
+        let transport = InMemoryTransport::new();
+        let path = "/PlatitorTvaRest/api/v8/ws/tva";
+        transport.respond(
+            Method::POST,
+            path,
+            HttpResponse::json(r#"{"cod":200,"message":"","found":[],"notFound":[19]}"#),
+        );
+        for _ in 0..2 {
+            transport.respond(
+                Method::POST,
+                path,
+                HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+            );
+        }
+
+        let client = AnafClient::builder()
+            .transport(transport)
+            .without_rate_limit()
+            .retry_policy(RetryPolicy::none())
+            .circuit_breaker(CircuitBreakerConfig::new(1, Duration::from_secs(60)))
+            .build()
+            .unwrap();
+        let api = client.vat_payer(Default::default());
+        let request = vec![ApiRequest::new(Cui::from_base(1), Utc::now().date_naive())];
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async {
+                assert!(api.send(request.clone()).await.is_ok());
+                assert!(matches!(
+                    api.send(request.clone()).await,
+                    Err(ApiError::ServiceUnavailable { .. })
+                ));
+                assert!(matches!(
+                    api.send(request.clone()).await,
+                    Err(ApiError::CircuitOpen { .. })
+                ));
+                assert!(matches!(
+                    api.send(vec![]).await,
+                    Err(ApiError::InvalidRequestError(0))
+                ));
+
+                // the raw response is returned, but the call still failed
+                let raw = api.send_raw(request).await.unwrap();
+                assert_eq!(raw.status(), StatusCode::SERVICE_UNAVAILABLE);
+            });
+        });
+
+        let counters = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter_map(|(key, _, _, value)| match value {
+                DebugValue::Counter(value) => {
+                    let key = key.key();
+                    let mut labels = key
+                        .labels()
+                        .map(|label| format!("{}={}", label.key(), label.value()))
+                        .collect::<Vec<_>>();
+                    labels.sort();
+
+                    Some((format!("{}{{{}}}", key.name(), labels.join(",")), value))
+                }
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        for (outcome, error, count) in [
+            ("success", "none", 1),
+            ("error", "service_unavailable", 2),
+            ("error", "circuit_open", 1),
+            ("error", "invalid_request", 1),
+        ] {
+            let labels = format!(
+                "{REQUESTS_TOTAL}{{api=vat_payer,error={error},outcome={outcome},version=v8}}"
+            );
+            assert_eq!(counters.get(&labels), Some(&count), "{labels}");
+        }
+        assert_eq!(
+            counters.get(&format!("{NOT_FOUND_TOTAL}{{api=vat_payer,version=v8}}")),
+            Some(&1)
+        );
+    }
+}
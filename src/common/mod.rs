@@ -1,30 +1,23 @@
-use thiserror::Error;
-
+mod batch;
+mod cui;
+mod date;
+mod error;
+mod family;
+mod instrument;
+mod raw;
 mod request;
 mod response;
-
+mod shape;
+mod text;
+
+pub(crate) use batch::*;
+pub use cui::*;
+pub use date::*;
+pub use error::*;
+pub use family::*;
+pub use instrument::*;
+pub use raw::*;
 pub use request::*;
 pub use response::*;
-
-pub type Result<T> = std::result::Result<T, ApiError>;
-
-#[derive(Debug, Error)]
-pub enum ApiError {
-    #[error("ANAF API is under maintenance")]
-    ServiceUnavailable,
-
-    #[error("ANAF API returned an error: {0}")]
-    ApiError(String),
-
-    #[error("ANAF API supports fetching between 1 and 500 companies, got {0}.")]
-    InvalidRequestError(usize),
-
-    #[error("Reqwest error: {0}")]
-    ReqwestError(#[from] reqwest::Error),
-
-    #[error("JSON error: {0}")]
-    JsonError(#[from] serde_json::Error),
-
-    #[error("Query String error: {0}")]
-    QueryStringError(#[from] serde_qs::Error),
-}
+pub use shape::*;
+pub(crate) use text::*;
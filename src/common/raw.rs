@@ -0,0 +1,102 @@
+use std::{fmt::Debug, marker::PhantomData, time::Duration};
+
+use chrono::{DateTime, Utc};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::time::Instant;
+
+use crate::{
+    check_body, read_response, record_status, tag_request, ApiResponse, AsyncApiResponse,
+    HttpRequest, HttpResponse, Result, Transport,
+};
+
+/// Typed response which can be parsed from what ANAF returned.
+pub trait FromRawResponse: Sized {
+    fn from_raw(response: &HttpResponse) -> Result<Self>;
+}
+
+impl<T: DeserializeOwned + Debug> FromRawResponse for ApiResponse<T> {
+    fn from_raw(response: &HttpResponse) -> Result<Self> {
+        read_response(response).and_then(check_body)
+    }
+}
+
+impl<T: DeserializeOwned + Debug> FromRawResponse for AsyncApiResponse<T> {
+    fn from_raw(response: &HttpResponse) -> Result<Self> {
+        read_response(response).and_then(check_body)
+    }
+}
+
+/// Response exactly as returned by ANAF, with the time it was requested at and how long it took.
+///
+/// Nothing is checked, so error statuses are returned as well. [`RawResponse::parse`] turns it
+/// into the typed response later, and an archived body can be parsed again with
+/// [`FromRawResponse::from_raw`]:
+///
+/// ```rust
+/// # fn main() -> anaf_api::Result<()> {
+/// use anaf_api::{vat_payer::VatPayerResponse, FromRawResponse, HttpResponse};
+///
+/// let archived = br#"{"cod":200,"message":"","found":[],"notFound":[111111115]}"#;
+/// let response = VatPayerResponse::from_raw(&HttpResponse::json(&archived[..]))?;
+///
+/// assert_eq!(response.not_found, vec![111111115]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RawResponse<T> {
+    pub url: String,
+    pub requested_at: DateTime<Utc>,
+    pub latency: Duration,
+    pub response: HttpResponse,
+    request_id: String,
+    parsed: PhantomData<fn() -> T>,
+}
+
+impl<T> RawResponse<T> {
+    /// Sends the request, timing it.
+    pub(crate) async fn fetch(transport: &dyn Transport, mut request: HttpRequest) -> Result<Self> {
+        let request_id = tag_request(&mut request);
+        let url = request.url.clone();
+        let requested_at = Utc::now();
+        let started_at = Instant::now();
+
+        let response = transport.send(request).await?;
+        record_status(response.status);
+
+        Ok(Self {
+            url,
+            requested_at,
+            latency: started_at.elapsed(),
+            response,
+            request_id,
+            parsed: PhantomData,
+        })
+    }
+
+    /// Id the call was sent with, in the [`crate::REQUEST_ID_HEADER`] header, and recorded on its
+    /// `anaf.request` span.
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.response.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.response.headers
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.response.body
+    }
+}
+
+impl<T: FromRawResponse> RawResponse<T> {
+    /// Parses the typed response, failing the same way as the typed call would.
+    pub fn parse(&self) -> Result<T> {
+        T::from_raw(&self.response)
+    }
+}
@@ -1,16 +1,21 @@
 use chrono::NaiveDate;
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+use crate::Cui;
+
+/// The maximum number of companies ANAF accepts in a single request.
+pub const MAX_REQUEST_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ApiRequest {
     #[serde(rename = "cui")]
-    pub registration_code: usize,
+    pub registration_code: Cui,
     #[serde(rename = "data")]
     pub when: NaiveDate,
 }
 
 impl ApiRequest {
-    pub fn new(registration_code: usize, when: NaiveDate) -> Self {
+    pub fn new(registration_code: Cui, when: NaiveDate) -> Self {
         Self {
             registration_code,
             when,
@@ -1,6 +1,51 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+use crate::{AnafErrorKind, ApiError, Cui, Result};
+
+/// Response which carries ANAF's own status code and message in its body.
+pub trait BodyStatus {
+    /// The `cod` field, which mirrors an HTTP status.
+    fn code(&self) -> usize;
+
+    /// The `message` field.
+    fn message(&self) -> &str;
+
+    /// How many companies were found, even if the code reports an error.
+    fn found(&self) -> usize {
+        0
+    }
+
+    /// The companies which were not found.
+    fn not_found(&self) -> &[Cui] {
+        &[]
+    }
+}
+
+/// Accepts a body whose `cod` is 200, or which still holds some companies despite an error code.
+pub(crate) fn check_body<T: BodyStatus>(response: T) -> Result<T> {
+    if response.code() == 200 {
+        return Ok(response);
+    }
+
+    if response.found() > 0 {
+        tracing::warn!(
+            "ANAF API answered with code {} but found {} companies: {}",
+            response.code(),
+            response.found(),
+            response.message()
+        );
+        return Ok(response);
+    }
+
+    Err(ApiError::Refused {
+        code: response.code(),
+        kind: AnafErrorKind::from_message(response.message()),
+        message: response.message().to_owned(),
+        not_found: response.not_found().to_vec(),
+    })
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ApiResponse<T> {
     #[serde(alias = "cod")]
     pub status: usize,
@@ -12,10 +57,36 @@ pub struct ApiResponse<T> {
     pub data: Vec<T>,
 
     #[serde(alias = "notFound")]
-    pub not_found: Vec<usize>,
+    pub not_found: Vec<Cui>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl<T> BodyStatus for ApiResponse<T> {
+    fn code(&self) -> usize {
+        self.status
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn found(&self) -> usize {
+        self.data.len()
+    }
+
+    fn not_found(&self) -> &[Cui] {
+        &self.not_found
+    }
+}
+
+impl<T> ApiResponse<T> {
+    /// Appends the found and not found companies of another response to this one.
+    pub fn merge(&mut self, other: ApiResponse<T>) {
+        self.data.extend(other.data);
+        self.not_found.extend(other.not_found);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AsyncApiResponse<T> {
     #[serde(alias = "cod")]
     pub status: usize,
@@ -26,3 +97,13 @@ pub struct AsyncApiResponse<T> {
     #[serde(alias = "correlationId")]
     pub token: T,
 }
+
+impl<T> BodyStatus for AsyncApiResponse<T> {
+    fn code(&self) -> usize {
+        self.status
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+
+use crate::{ApiResponse, ANAF_DATE_FORMAT};
+
+/// Response which can be serialized back into the JSON shape ANAF sends.
+///
+/// The regular [`serde::Serialize`] implementations use the field names of the structs and
+/// `null` for missing values. This uses ANAF's Romanian field names instead, with empty strings
+/// for missing texts and dates, except for the few dates ANAF sends as `null`, so the output can
+/// be handed to code expecting ANAF's payloads:
+///
+/// ```rust
+/// # fn main() -> anaf_api::Result<()> {
+/// use anaf_api::{vat_payer::VatPayerResponse, FromRawResponse, HttpResponse, ToAnafJson};
+///
+/// let body = r#"{"cod":200,"message":"SUCCESS","found":[],"notFound":[49201783]}"#;
+/// let response = VatPayerResponse::from_raw(&HttpResponse::json(body))?;
+///
+/// assert_eq!(response.to_anaf_json(), serde_json::from_str::<serde_json::Value>(body)?);
+/// # Ok(())
+/// # }
+/// ```
+pub trait ToAnafJson {
+    fn to_anaf_json(&self) -> Value;
+}
+
+impl<T: ToAnafJson> ToAnafJson for ApiResponse<T> {
+    fn to_anaf_json(&self) -> Value {
+        json!({
+            "cod": self.status,
+            "message": self.message,
+            "found": self.data.iter().map(ToAnafJson::to_anaf_json).collect::<Vec<_>>(),
+            "notFound": self.not_found,
+        })
+    }
+}
+
+/// Renders a missing text the way ANAF does.
+pub(crate) fn text(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or_default()
+}
+
+/// Renders a date the way ANAF does, with an empty string when it is missing.
+pub(crate) fn date(value: &Option<NaiveDate>) -> String {
+    value
+        .map(|value| value.format(ANAF_DATE_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+/// Renders a date the way ANAF does in the few fields where it sends `null` when it is missing.
+#[cfg(any(feature = "cults_api", feature = "farmers_api"))]
+pub(crate) fn nullable_date(value: &Option<NaiveDate>) -> Value {
+    match value {
+        Some(_) => json!(date(value)),
+        None => Value::Null,
+    }
+}
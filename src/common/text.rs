@@ -0,0 +1,14 @@
+use serde::{Deserialize, Deserializer};
+
+/// Deserializes an optional ANAF text, trimmed. Empty strings and `null`, which ANAF sends for
+/// missing values, become `None`.
+pub(crate) fn deserialize_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+
+    Ok(value
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty()))
+}
@@ -1,8 +1,16 @@
 mod apis;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cache;
 mod client;
 mod common;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
 
-pub use client::AnafClient;
+pub use client::*;
 
 pub use apis::*;
+pub use cache::*;
 pub use common::*;
+pub use transport::*;
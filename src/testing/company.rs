@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+
+use crate::{Cui, ANAF_DATE_FORMAT};
+
+/// Company served by [`crate::testing::FakeAnaf`].
+///
+/// Only the fields most tests care about can be set; the rest of the ANAF payload is filled in
+/// with empty values, the way ANAF does for missing data.
+#[derive(Debug, Clone)]
+pub struct FakeCompany {
+    pub registration_code: Cui,
+    pub name: String,
+    pub address: String,
+    pub registry_number: String,
+    pub activity_code: usize,
+    pub activity_name: String,
+    pub registered_at: NaiveDate,
+    /// VAT registration periods, as (start, cancellation).
+    pub vat_periods: Vec<(NaiveDate, Option<NaiveDate>)>,
+    pub vat_on_collection: bool,
+    pub split_vat: bool,
+    pub inactive: bool,
+    pub einvoice: bool,
+    pub cult: bool,
+    pub farmer: bool,
+    /// Balance indicators per year, as (code, name, value).
+    pub balances: BTreeMap<usize, Vec<(String, String, isize)>>,
+}
+
+impl FakeCompany {
+    pub fn new(registration_code: Cui, name: &str) -> Self {
+        Self {
+            registration_code,
+            name: name.to_owned(),
+            address: "MUNICIPIUL BUCUREŞTI, SECTOR 1, STR. EXEMPLU, NR.1".to_owned(),
+            registry_number: "J40/1/2020".to_owned(),
+            activity_code: 6201,
+            activity_name: "Activitati de realizare a soft-ului la comanda".to_owned(),
+            registered_at: NaiveDate::from_ymd_opt(2020, 1, 1).expect("the date is valid"),
+            vat_periods: vec![],
+            vat_on_collection: false,
+            split_vat: false,
+            inactive: false,
+            einvoice: false,
+            cult: false,
+            farmer: false,
+            balances: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_vat_payer_since(mut self, since: NaiveDate) -> Self {
+        self.vat_periods.push((since, None));
+        self
+    }
+
+    /// Adds a VAT registration period which was cancelled on `until`.
+    pub fn with_vat_period(mut self, since: NaiveDate, until: NaiveDate) -> Self {
+        self.vat_periods.push((since, Some(until)));
+        self
+    }
+
+    pub fn with_inactive(mut self, inactive: bool) -> Self {
+        self.inactive = inactive;
+        self
+    }
+
+    pub fn with_einvoice(mut self, einvoice: bool) -> Self {
+        self.einvoice = einvoice;
+        self
+    }
+
+    /// Lists the company in the registry of religious entities (RegCult).
+    pub fn with_cult(mut self, cult: bool) -> Self {
+        self.cult = cult;
+        self
+    }
+
+    /// Lists the company in the registry of farmers (RegAgric).
+    pub fn with_farmer(mut self, farmer: bool) -> Self {
+        self.farmer = farmer;
+        self
+    }
+
+    /// Publishes the balance of the given year, with indicators given as (code, value).
+    pub fn with_balance(mut self, year: usize, indicators: &[(&str, isize)]) -> Self {
+        let indicators = indicators
+            .iter()
+            .map(|(code, value)| ((*code).to_owned(), format!("Indicator {code}"), *value))
+            .collect();
+
+        self.balances.insert(year, indicators);
+        self
+    }
+
+    pub(crate) fn vat_payer(&self, when: &str, v8: bool) -> Value {
+        let is_payer = NaiveDate::parse_from_str(when, ANAF_DATE_FORMAT).is_ok_and(|when| {
+            self.vat_periods
+                .iter()
+                .any(|(since, until)| *since <= when && until.is_none_or(|until| when < until))
+        });
+
+        let mut general = json!({
+            "cui": self.registration_code,
+            "data": when,
+            "denumire": self.name,
+            "adresa": self.address,
+            "nrRegCom": self.registry_number,
+            "telefon": "",
+            "fax": "",
+            "codPostal": "",
+            "act": "",
+            "stare_inregistrare": format!("INREGISTRAT din data {}", self.registered_at),
+            "data_inregistrare": self.registered_at.to_string(),
+            "cod_CAEN": self.activity_code.to_string(),
+            "iban": "",
+            "statusRO_e_Factura": self.einvoice,
+        });
+        let mut scope = json!({ "scpTVA": is_payer });
+
+        if v8 {
+            general["organFiscalCompetent"] = json!("Administraţia Sector 1 a Finanţelor Publice");
+            general["forma_de_proprietate"] = json!("");
+            general["forma_organizare"] = json!("");
+            general["forma_juridica"] = json!("");
+
+            scope["perioade_TVA"] = self
+                .vat_periods
+                .iter()
+                .rev()
+                .map(|(since, until)| {
+                    json!({
+                        "data_inceput_ScpTVA": since.to_string(),
+                        "data_sfarsit_ScpTVA": until.map(|until| until.to_string()).unwrap_or_default(),
+                        "data_anul_imp_ScpTVA": until.map(|until| until.to_string()).unwrap_or_default(),
+                        "mesaj_ScpTVA": "",
+                    })
+                })
+                .collect();
+        } else {
+            let (since, until) = match self.vat_periods.last() {
+                Some((since, until)) => (since.to_string(), until.map(|until| until.to_string())),
+                None => (String::new(), None),
+            };
+            let until = until.unwrap_or_default();
+
+            scope["data_inceput_ScpTVA"] = json!(since);
+            scope["data_sfarsit_ScpTVA"] = json!(until);
+            scope["data_anul_imp_ScpTVA"] = json!(until);
+            scope["mesaj_ScpTVA"] = json!("");
+        }
+
+        json!({
+            "date_generale": general,
+            "inregistrare_scop_Tva": scope,
+            "inregistrare_RTVAI": {
+                "dataInceputTvaInc": "",
+                "dataSfarsitTvaInc": "",
+                "dataActualizareTvaInc": "",
+                "dataPublicareTvaInc": "",
+                "tipActTvaInc": "",
+                "statusTvaIncasare": self.vat_on_collection,
+            },
+            "stare_inactiv": {
+                "dataInactivare": "",
+                "dataReactivare": "",
+                "dataPublicare": "",
+                "dataRadiere": "",
+                "statusInactivi": self.inactive,
+            },
+            "inregistrare_SplitTVA": {
+                "dataInceputSplitTVA": "",
+                "dataAnulareSplitTVA": "",
+                "statusSplitTVA": self.split_vat,
+            },
+            "adresa_sediu_social": self.address_json("s"),
+            "adresa_domiciliu_fiscal": self.address_json("d"),
+        })
+    }
+
+    fn address_json(&self, prefix: &str) -> Value {
+        let fields = [
+            ("denumire_Strada", "Str. Exemplu"),
+            ("numar_Strada", "1"),
+            ("denumire_Localitate", "Sector 1 Mun. Bucureşti"),
+            ("cod_Localitate", "403"),
+            ("denumire_Judet", "MUNICIPIUL BUCUREŞTI"),
+            ("cod_Judet", "40"),
+            ("cod_JudetAuto", "B"),
+            ("tara", ""),
+            ("detalii_Adresa", ""),
+            ("cod_Postal", ""),
+        ];
+
+        fields
+            .into_iter()
+            .map(|(name, value)| (format!("{prefix}{name}"), json!(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Renders the RegCult or RegAgric entry, which only differ by the suffix of a few fields.
+    pub(crate) fn registry(&self, when: &str, suffix: &str) -> Value {
+        let mut entry = json!({
+            "cui": self.registration_code,
+            "data": when,
+            "denumire": self.name,
+            "adresa": self.address,
+            "nrRegCom": self.registry_number,
+            "telefon": "",
+            "fax": "",
+            "codPostal": "",
+            "act": "",
+            "stare_inregistrare": format!("INREGISTRAT din data {}", self.registered_at),
+        });
+
+        entry[format!("dataInceputReg{suffix}")] = json!(self.registered_at.to_string());
+        entry[format!("dataAnulareReg{suffix}")] = Value::Null;
+        entry[format!("statusReg{suffix}")] = json!(true);
+
+        entry
+    }
+
+    pub(crate) fn balance(&self, year: usize) -> Value {
+        let indicators = self
+            .balances
+            .get(&year)
+            .into_iter()
+            .flatten()
+            .map(|(code, name, value)| {
+                json!({
+                    "indicator": code,
+                    "val_indicator": value,
+                    "val_den_indicator": name,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "an": year,
+            "cui": self.registration_code,
+            "deni": self.name,
+            "caen": self.activity_code,
+            "den_caen": self.activity_name,
+            "i": indicators,
+        })
+    }
+}
@@ -0,0 +1,9 @@
+//! Fake ANAF web services for integration tests.
+//!
+//! Enabled by the `testing` feature.
+
+mod company;
+mod server;
+
+pub use company::*;
+pub use server::*;
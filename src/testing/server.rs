@@ -0,0 +1,483 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{Method, StatusCode, Url};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{AnafClient, Cui, Result};
+
+use super::FakeCompany;
+
+/// Fault injected into the next request received by [`FakeAnaf`].
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Answers with `503 Service Unavailable`, as during ANAF's maintenance windows.
+    Unavailable,
+    /// Waits before answering normally.
+    Delay(Duration),
+    /// Answers with a truncated JSON body.
+    Malformed,
+    /// Answers with the given status and an empty body.
+    Status(StatusCode),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    companies: HashMap<Cui, FakeCompany>,
+    faults: VecDeque<Fault>,
+    pending_polls: usize,
+    jobs: HashMap<String, (Vec<Value>, usize)>,
+    received: Vec<String>,
+}
+
+/// Local HTTP server which mimics the ANAF web services.
+///
+/// Serves the VAT payer (v7 and v8), async VAT payer, balance, RegCult and RegAgric endpoints
+/// from an in-memory dataset of [`FakeCompany`], and can inject [`Fault`]s. The server stops when
+/// dropped.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # async fn run() -> anaf_api::Result<()> {
+/// use anaf_api::{
+///     testing::{FakeAnaf, FakeCompany, Fault},
+///     ApiRequest,
+/// };
+///
+/// let anaf = FakeAnaf::start().await?;
+/// let cui = "RO40914732".parse()?;
+/// anaf.add_company(FakeCompany::new(cui, "COMPANY SRL"));
+/// anaf.fail_next(Fault::Unavailable);
+///
+/// let when = chrono::Utc::now().date_naive();
+/// let response = anaf
+///     .client()
+///     .vat_payer(Default::default())
+///     .send(vec![ApiRequest::new(cui, when)])
+///     .await;
+///
+/// assert!(response.is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FakeAnaf {
+    url: String,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl FakeAnaf {
+    /// Starts the server on a random local port.
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let task = tokio::spawn({
+            let state = state.clone();
+
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(serve(stream, state.clone()));
+                }
+            }
+        });
+
+        Ok(Self { url, state, task })
+    }
+
+    /// Returns the base URL, to be set with [`crate::AnafClientBuilder::base_url`].
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns a client which sends its requests to this server, without rate limiting.
+    pub fn client(&self) -> AnafClient {
+        AnafClient::builder()
+            .base_url(&self.url)
+            .without_rate_limit()
+            .build()
+            .expect("the fake client configuration is valid")
+    }
+
+    pub fn add_company(&self, company: FakeCompany) {
+        self.state()
+            .companies
+            .insert(company.registration_code, company);
+    }
+
+    pub fn remove_company(&self, registration_code: Cui) {
+        self.state().companies.remove(&registration_code);
+    }
+
+    /// Injects a fault into the next request. Several faults are applied in order.
+    pub fn fail_next(&self, fault: Fault) {
+        self.state().faults.push_back(fault);
+    }
+
+    /// Sets how many polls of the async VAT payer requests submitted from now on answer that the
+    /// result is not ready.
+    pub fn set_pending_polls(&self, pending_polls: usize) {
+        self.state().pending_polls = pending_polls;
+    }
+
+    /// Returns the requests received so far, as `METHOD /path`.
+    pub fn received(&self) -> Vec<String> {
+        self.state().received.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("fake ANAF lock is poisoned")
+    }
+}
+
+impl Drop for FakeAnaf {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct Request {
+    method: Method,
+    url: Url,
+    body: Vec<u8>,
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut stream = BufReader::new(stream);
+
+    let Ok(Some(request)) = read_request(&mut stream).await else {
+        return;
+    };
+
+    let fault = {
+        let mut state = state.lock().expect("fake ANAF lock is poisoned");
+        state
+            .received
+            .push(format!("{} {}", request.method, request.url.path()));
+        state.faults.pop_front()
+    };
+
+    let (status, body) = match fault {
+        Some(Fault::Unavailable) => (StatusCode::SERVICE_UNAVAILABLE, String::new()),
+        Some(Fault::Malformed) => (StatusCode::OK, r#"{"cod":200,"found":[{"#.to_owned()),
+        Some(Fault::Status(status)) => (status, String::new()),
+        Some(Fault::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            route(&request, &state)
+        }
+        None => route(&request, &state),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default(),
+        body.len(),
+        body
+    );
+
+    let _ = stream.get_mut().write_all(response.as_bytes()).await;
+    let _ = stream.get_mut().shutdown().await;
+}
+
+/// Reads an HTTP/1.1 request, with its body if it has a `content-length`.
+async fn read_request(stream: &mut BufReader<TcpStream>) -> std::io::Result<Option<Request>> {
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if stream.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+
+    let (Ok(method), Ok(url)) = (
+        method.parse::<Method>(),
+        Url::parse(&format!("http://localhost{target}")),
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(Some(Request { method, url, body }))
+}
+
+fn route(request: &Request, state: &Mutex<State>) -> (StatusCode, String) {
+    let mut state = state.lock().expect("fake ANAF lock is poisoned");
+    let path = request.url.path();
+    let query = request
+        .url
+        .query_pairs()
+        .into_owned()
+        .collect::<HashMap<_, _>>();
+
+    let response = match (&request.method, path) {
+        (&Method::POST, "/PlatitorTvaRest/api/v8/ws/tva") => {
+            registry(&state, &request.body, |company, when| {
+                Some(company.vat_payer(when, true))
+            })
+        }
+        (&Method::POST, "/PlatitorTvaRest/api/v7/ws/tva") => {
+            registry(&state, &request.body, |company, when| {
+                Some(company.vat_payer(when, false))
+            })
+        }
+        (&Method::POST, "/RegCult/api/v2/ws/cult") => {
+            registry(&state, &request.body, |company, when| {
+                company.cult.then(|| company.registry(when, "Cult"))
+            })
+        }
+        (&Method::POST, "/RegAgric/api/v2/ws/agric") => {
+            registry(&state, &request.body, |company, when| {
+                company.farmer.then(|| company.registry(when, "Agric"))
+            })
+        }
+        (&Method::POST, "/AsynchWebService/api/v8/ws/tva") => {
+            match serde_json::from_slice::<Vec<Value>>(&request.body) {
+                Ok(companies) => {
+                    let id = format!("fake-{}", state.jobs.len() + 1);
+                    let pending_polls = state.pending_polls;
+                    state.jobs.insert(id.clone(), (companies, pending_polls));
+
+                    Some(json!({ "cod": 200, "message": "SUCCESS", "correlationId": id }))
+                }
+                Err(_) => None,
+            }
+        }
+        (&Method::GET, "/AsynchWebService/api/v8/ws/tva") => {
+            let job = query.get("id").and_then(|id| state.jobs.get_mut(id));
+
+            match job {
+                Some((_, pending_polls)) if *pending_polls > 0 => {
+                    *pending_polls -= 1;
+                    Some(json!({ "cod": 200, "message": "Cererea nu a fost procesata inca" }))
+                }
+                Some((companies, _)) => {
+                    let body = serde_json::to_vec(companies).unwrap_or_default();
+
+                    registry(&state, &body, |company, when| {
+                        Some(company.vat_payer(when, true))
+                    })
+                }
+                None => return (StatusCode::NOT_FOUND, String::new()),
+            }
+        }
+        (&Method::GET, "/bilant") => {
+            let registration_code = query
+                .get("cui")
+                .and_then(|it| serde_json::from_str::<Cui>(it).ok());
+            let year = query.get("an").and_then(|it| it.parse().ok());
+
+            match (registration_code, year) {
+                (Some(registration_code), Some(year)) => {
+                    let company = state
+                        .companies
+                        .get(&registration_code)
+                        .cloned()
+                        .unwrap_or_else(|| FakeCompany::new(registration_code, ""));
+
+                    Some(company.balance(year))
+                }
+                _ => None,
+            }
+        }
+        _ => return (StatusCode::NOT_FOUND, String::new()),
+    };
+
+    match response {
+        Some(response) => (StatusCode::OK, response.to_string()),
+        None => (
+            StatusCode::BAD_REQUEST,
+            json!({ "cod": 400, "message": "Cerere invalida" }).to_string(),
+        ),
+    }
+}
+
+/// Answers a registry lookup, with the entry of each company given by `entry`.
+fn registry(
+    state: &State,
+    body: &[u8],
+    entry: impl Fn(&FakeCompany, &str) -> Option<Value>,
+) -> Option<Value> {
+    let request = serde_json::from_slice::<Vec<Value>>(body).ok()?;
+
+    if request.len() > crate::MAX_REQUEST_SIZE {
+        return Some(json!({
+            "cod": 400,
+            "message": "Numarul maxim de CUI-uri admise este 500",
+            "found": [],
+            "notFound": [],
+        }));
+    }
+
+    let mut found = vec![];
+    let mut not_found = vec![];
+
+    for it in request {
+        let registration_code = serde_json::from_value::<Cui>(it.get("cui")?.clone()).ok()?;
+        let when = it.get("data")?.as_str()?;
+
+        match state
+            .companies
+            .get(&registration_code)
+            .and_then(|company| entry(company, when))
+        {
+            Some(entry) => found.push(entry),
+            None => not_found.push(registration_code),
+        }
+    }
+
+    Some(json!({
+        "cod": 200,
+        "message": "SUCCESS",
+        "found": found,
+        "notFound": not_found,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::NaiveDate;
+
+    use crate::{AnafClient, ApiError, ApiRequest, Cui, RetryPolicy};
+
+    use super::{FakeAnaf, FakeCompany, Fault};
+
+    fn when() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+    }
+
+    #[cfg(all(
+        feature = "vat_payer_async_api",
+        feature = "balance_api",
+        feature = "cults_api",
+        feature = "farmers_api"
+    ))]
+    #[tokio::test]
+    async fn fake_serves_every_api() {
+        let (first, second) = (Cui::from_base(1), Cui::from_base(2));
+        let anaf = FakeAnaf::start().await.unwrap();
+        anaf.add_company(
+            FakeCompany::new(first, "COMPANY SRL")
+                .with_vat_payer_since(when())
+                .with_farmer(true)
+                .with_balance(2023, &[("I1", 1000)]),
+        );
+        let client = anaf.client();
+
+        let request = vec![
+            ApiRequest::new(first, when()),
+            ApiRequest::new(second, when()),
+        ];
+
+        let response = client
+            .vat_payer(Default::default())
+            .send(request.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.data[0].company_info.name, "COMPANY SRL");
+        assert!(response.data[0].vat_scope.is_payer);
+        assert!(response.data[0].was_vat_payer_on(when()));
+        assert_eq!(response.not_found, vec![second]);
+
+        let response = client
+            .cult(Default::default())
+            .send(request.clone())
+            .await
+            .unwrap();
+        assert!(response.data.is_empty());
+
+        let response = client
+            .farmer(Default::default())
+            .send(request.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.data[0].unique_registration_code, first);
+
+        let api = client.async_vat_payer(Default::default());
+        let token = api.send(request).await.unwrap().token;
+        let response = api.try_fetch(&token).await.unwrap().unwrap();
+        assert_eq!(response.data.len(), 1);
+
+        let response = client
+            .balance(Default::default())
+            .send(crate::balance::BalanceRequest::new(first, 2023))
+            .await
+            .unwrap();
+        assert_eq!(response.name, "COMPANY SRL");
+    }
+
+    #[tokio::test]
+    async fn fake_injects_faults() {
+        let anaf = FakeAnaf::start().await.unwrap();
+        anaf.add_company(FakeCompany::new(Cui::from_base(1), "COMPANY SRL"));
+        anaf.fail_next(Fault::Unavailable);
+        anaf.fail_next(Fault::Malformed);
+        anaf.fail_next(Fault::Delay(Duration::from_millis(10)));
+
+        let client = AnafClient::builder()
+            .base_url(anaf.url())
+            .without_rate_limit()
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+        let api = client.vat_payer(Default::default());
+        let request = vec![ApiRequest::new(Cui::from_base(1), when())];
+
+        let response = api.send(request.clone()).await;
+        assert!(matches!(response, Err(ApiError::ServiceUnavailable { .. })));
+
+        let response = api.send(request.clone()).await;
+        assert!(matches!(response, Err(ApiError::MalformedResponse { .. })));
+
+        let response = api.send(request).await.unwrap();
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(anaf.received().len(), 3);
+    }
+
+    #[cfg(feature = "vat_payer_async_api")]
+    #[tokio::test]
+    async fn fake_delays_async_results() {
+        let anaf = FakeAnaf::start().await.unwrap();
+        anaf.add_company(FakeCompany::new(Cui::from_base(1), "COMPANY SRL"));
+
+        let api = anaf.client().async_vat_payer(Default::default());
+        let request = vec![ApiRequest::new(Cui::from_base(1), when())];
+
+        let token = api.send(request.clone()).await.unwrap().token;
+        assert!(api.try_fetch(&token).await.unwrap().is_some());
+
+        anaf.set_pending_polls(1);
+        let token = api.send(request).await.unwrap().token;
+        assert!(api.try_fetch(&token).await.unwrap().is_none());
+        assert!(api.try_fetch(&token).await.unwrap().is_some());
+    }
+}
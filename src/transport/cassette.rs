@@ -0,0 +1,383 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, SET_COOKIE},
+    StatusCode, Url,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ApiError, Result};
+
+use super::{HttpRequest, HttpResponse, Transport, TransportFuture};
+
+/// Value written instead of the redacted headers and fields.
+pub const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record(Arc<dyn Transport>),
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    cassettes: HashMap<String, Vec<Interaction>>,
+    /// Indexes of the interactions already replayed, per cassette.
+    replayed: HashMap<String, HashSet<usize>>,
+}
+
+/// [`Transport`] which records the ANAF interactions to cassette files, or replays them.
+///
+/// Each API gets its own cassette, named after the first segment of the URL path, e.g.
+/// `PlatitorTvaRest.json` or `bilant.json`, holding the request and response pairs in order.
+/// JSON bodies are kept as JSON, so cassettes can be reviewed and edited by hand.
+///
+/// In replay mode a request is matched on its method, URL, query parameters and JSON body, and
+/// identical requests are answered in the order they were recorded. A request without a match
+/// fails with [`ApiError::UnrecordedRequest`].
+///
+/// Redacted headers and JSON fields are replaced with [`REDACTED`] before being written. The
+/// same redactions must be set when replaying, so requests match what was recorded.
+///
+/// Usage:
+///
+/// ```rust,no_run
+/// # fn main() -> anaf_api::Result<()> {
+/// use anaf_api::{AnafClient, CassetteTransport, ReqwestTransport};
+///
+/// let transport = match std::env::var("ANAF_RECORD").is_ok() {
+///     true => CassetteTransport::record(ReqwestTransport::default(), "tests/cassettes")?,
+///     false => CassetteTransport::replay("tests/cassettes"),
+/// };
+///
+/// let client = AnafClient::builder()
+///     .transport(transport.with_redacted_field("iban"))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CassetteTransport {
+    directory: PathBuf,
+    mode: Mode,
+    redacted_headers: Vec<HeaderName>,
+    redacted_fields: Vec<String>,
+    state: Mutex<State>,
+}
+
+impl CassetteTransport {
+    /// Sends the requests through `inner` and records them in `directory`, replacing the
+    /// cassettes recorded before.
+    pub fn record(inner: impl Transport + 'static, directory: impl AsRef<Path>) -> Result<Self> {
+        fs::create_dir_all(directory.as_ref())?;
+
+        Ok(Self::new(directory, Mode::Record(Arc::new(inner))))
+    }
+
+    /// Replays the cassettes recorded in `directory`, without touching the network.
+    pub fn replay(directory: impl AsRef<Path>) -> Self {
+        Self::new(directory, Mode::Replay)
+    }
+
+    fn new(directory: impl AsRef<Path>, mode: Mode) -> Self {
+        Self {
+            directory: directory.as_ref().to_owned(),
+            mode,
+            redacted_headers: vec![AUTHORIZATION, COOKIE, SET_COOKIE],
+            redacted_fields: vec![],
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Redacts a response header. `Authorization`, `Cookie` and `Set-Cookie` are always redacted.
+    pub fn with_redacted_header(mut self, name: HeaderName) -> Self {
+        self.redacted_headers.push(name);
+        self
+    }
+
+    /// Redacts a field of the JSON bodies, at any depth, in both requests and responses.
+    pub fn with_redacted_field(mut self, name: &str) -> Self {
+        self.redacted_fields.push(name.to_owned());
+        self
+    }
+
+    async fn record_interaction(
+        &self,
+        inner: &dyn Transport,
+        request: HttpRequest,
+    ) -> Result<HttpResponse> {
+        let (cassette, recorded) = self.recorded_request(&request)?;
+        let response = inner.send(request).await?;
+
+        let interaction = Interaction {
+            request: recorded,
+            response: self.recorded_response(&response),
+        };
+
+        let mut state = self.state.lock().expect("cassette lock is poisoned");
+        let interactions = state.cassettes.entry(cassette.clone()).or_default();
+        interactions.push(interaction);
+
+        fs::write(
+            self.path(&cassette),
+            serde_json::to_vec_pretty(interactions)?,
+        )?;
+
+        Ok(response)
+    }
+
+    fn replay_interaction(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let (cassette, recorded) = self.recorded_request(request)?;
+        let mut state = self.state.lock().expect("cassette lock is poisoned");
+
+        if !state.cassettes.contains_key(&cassette) {
+            let interactions = match fs::read(self.path(&cassette)) {
+                Ok(contents) => serde_json::from_slice(&contents)?,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => vec![],
+                Err(error) => return Err(error.into()),
+            };
+            state.cassettes.insert(cassette.clone(), interactions);
+        }
+
+        let State {
+            cassettes,
+            replayed,
+        } = &mut *state;
+        let replayed = replayed.entry(cassette.clone()).or_default();
+        let matches = cassettes[&cassette]
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request == recorded)
+            .collect::<Vec<_>>();
+
+        let Some((index, interaction)) = matches
+            .iter()
+            .find(|(index, _)| !replayed.contains(index))
+            .or(matches.last())
+        else {
+            return Err(ApiError::UnrecordedRequest {
+                cassette: self.path(&cassette).display().to_string(),
+                method: request.method.clone(),
+                url: request.url.clone(),
+            });
+        };
+
+        replayed.insert(*index);
+
+        Ok(replayed_response(&interaction.response))
+    }
+
+    /// Returns the cassette of the request and the request as it is recorded, with its query
+    /// parameters sorted and its body redacted.
+    fn recorded_request(&self, request: &HttpRequest) -> Result<(String, RecordedRequest)> {
+        let mut url = Url::parse(&request.url)
+            .map_err(|error| ApiError::TransportError(error.to_string()))?;
+
+        let cassette = url
+            .path_segments()
+            .and_then(|mut segments| segments.next())
+            .filter(|segment| !segment.is_empty())
+            .unwrap_or("root")
+            .to_owned();
+
+        let mut query = url.query_pairs().into_owned().collect::<Vec<_>>();
+        query.sort();
+        match query.is_empty() {
+            true => url.set_query(None),
+            false => {
+                url.query_pairs_mut().clear().extend_pairs(query);
+            }
+        }
+
+        let body = request.body.as_deref().map(|body| self.body(body));
+
+        Ok((
+            cassette,
+            RecordedRequest {
+                method: request.method.to_string(),
+                url: url.to_string(),
+                body,
+            },
+        ))
+    }
+
+    fn recorded_response(&self, response: &HttpResponse) -> RecordedResponse {
+        let headers = response
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = match self.redacted_headers.contains(name) {
+                    true => REDACTED.to_owned(),
+                    false => String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                };
+
+                (name.to_string(), value)
+            })
+            .collect();
+
+        RecordedResponse {
+            status: response.status.as_u16(),
+            headers,
+            body: self.body(&response.body),
+        }
+    }
+
+    /// Keeps JSON objects and arrays as JSON, and anything else as text.
+    fn body(&self, body: &[u8]) -> Value {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(mut value) if value.is_object() || value.is_array() => {
+                redact(&mut value, &self.redacted_fields);
+                value
+            }
+            _ => Value::String(String::from_utf8_lossy(body).into_owned()),
+        }
+    }
+
+    fn path(&self, cassette: &str) -> PathBuf {
+        self.directory.join(format!("{cassette}.json"))
+    }
+}
+
+impl Transport for CassetteTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            match &self.mode {
+                Mode::Record(inner) => self.record_interaction(inner.as_ref(), request).await,
+                Mode::Replay => self.replay_interaction(&request),
+            }
+        })
+    }
+}
+
+fn redact(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(object) => {
+            for (name, value) in object.iter_mut() {
+                match fields.contains(name) {
+                    true => *value = Value::String(REDACTED.to_owned()),
+                    false => redact(value, fields),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| redact(value, fields)),
+        _ => {}
+    }
+}
+
+fn replayed_response(recorded: &RecordedResponse) -> HttpResponse {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &recorded.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+
+    let body = match &recorded.body {
+        Value::String(text) => text.clone().into_bytes(),
+        value => value.to_string().into_bytes(),
+    };
+
+    HttpResponse {
+        status: StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::OK),
+        headers,
+        body,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use reqwest::Method;
+
+    use crate::{
+        AnafClient, ApiError, ApiRequest, Cui, HttpResponse, InMemoryTransport, RetryPolicy,
+    };
+
+    use super::{CassetteTransport, REDACTED};
+
+    const RESPONSE: &str = r#"{"cod":200,"message":"","found":[],"notFound":[19,27],"iban":"RO49AAAA1B31007593840000"}"#;
+
+    fn client(transport: CassetteTransport) -> AnafClient {
+        AnafClient::builder()
+            .transport(transport.with_redacted_field("iban"))
+            .without_rate_limit()
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn cassette_replays_recorded_interactions() {
+        let directory = std::env::temp_dir().join(format!("anaf-cassettes-{}", std::process::id()));
+        let when = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let request = vec![
+            ApiRequest::new(Cui::from_base(1), when),
+            ApiRequest::new(Cui::from_base(2), when),
+        ];
+
+        let upstream = InMemoryTransport::new();
+        upstream.respond(
+            Method::POST,
+            "/PlatitorTvaRest/api/v8/ws/tva",
+            HttpResponse::json(RESPONSE),
+        );
+
+        let recorded = client(CassetteTransport::record(upstream, &directory).unwrap())
+            .vat_payer(Default::default())
+            .send_raw(request.clone())
+            .await
+            .unwrap();
+        assert!(recorded.response.text().contains("RO49"));
+
+        let cassette = std::fs::read_to_string(directory.join("PlatitorTvaRest.json")).unwrap();
+        assert!(!cassette.contains("RO49"));
+        assert!(cassette.contains(REDACTED));
+
+        let replay = client(CassetteTransport::replay(&directory));
+        let response = replay
+            .vat_payer(Default::default())
+            .send(request)
+            .await
+            .unwrap();
+        assert_eq!(response.not_found, vec![19, 27]);
+
+        let response = replay
+            .vat_payer(Default::default())
+            .send(vec![ApiRequest::new(Cui::from_base(3), when)])
+            .await;
+        assert!(matches!(response, Err(ApiError::UnrecordedRequest { .. })));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use reqwest::{Method, Url};
+
+use crate::ApiError;
+
+use super::{HttpRequest, HttpResponse, Transport, TransportFuture};
+
+#[derive(Debug)]
+struct Route {
+    method: Method,
+    path: String,
+    responses: VecDeque<HttpResponse>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<HttpRequest>,
+}
+
+/// [`Transport`] which serves canned responses without touching the network.
+///
+/// Responses are registered per method and URL path, ignoring the host and the query. When
+/// several responses are registered for the same route they are served in order, and the last
+/// one keeps being served afterwards. Every request is recorded and can be inspected later.
+///
+/// Usage:
+///
+/// ```rust
+/// # fn main() -> anaf_api::Result<()> {
+/// use anaf_api::{AnafClient, HttpResponse, InMemoryTransport};
+/// use reqwest::Method;
+///
+/// let transport = InMemoryTransport::new();
+/// transport.respond(
+///     Method::POST,
+///     "/PlatitorTvaRest/api/v8/ws/tva",
+///     HttpResponse::json(r#"{"cod":200,"message":"","found":[],"notFound":[]}"#),
+/// );
+///
+/// let client = AnafClient::builder()
+///     .transport(transport.clone())
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTransport {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a response for the given method and URL path.
+    pub fn respond(&self, method: Method, path: &str, response: HttpResponse) {
+        let mut state = self.state.lock().expect("transport lock is poisoned");
+
+        match state
+            .routes
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+        {
+            Some(route) => route.responses.push_back(response),
+            None => state.routes.push(Route {
+                method,
+                path: path.to_owned(),
+                responses: VecDeque::from([response]),
+            }),
+        }
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.state
+            .lock()
+            .expect("transport lock is poisoned")
+            .requests
+            .clone()
+    }
+
+    fn serve(&self, request: HttpRequest) -> Result<HttpResponse, ApiError> {
+        let path = Url::parse(&request.url)
+            .map(|url| url.path().to_owned())
+            .map_err(|error| ApiError::TransportError(error.to_string()))?;
+
+        let mut state = self.state.lock().expect("transport lock is poisoned");
+        let method = request.method.clone();
+        state.requests.push(request);
+
+        let route = state
+            .routes
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+            .ok_or_else(|| {
+                ApiError::TransportError(format!("no response registered for {method} {path}"))
+            })?;
+
+        match route.responses.len() {
+            1 => Ok(route.responses[0].clone()),
+            _ => Ok(route.responses.pop_front().expect("routes have a response")),
+        }
+    }
+}
+
+impl Transport for InMemoryTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move { self.serve(request) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+    use reqwest::{Method, StatusCode};
+
+    use crate::{vat_payer::VatPayerApiVersion, AnafClient, ApiRequest, Cui};
+
+    use super::{HttpResponse, InMemoryTransport};
+
+    #[tokio::test]
+    async fn transport_serves_responses_in_order() {
+        let transport = InMemoryTransport::new();
+        transport.respond(
+            Method::POST,
+            "/PlatitorTvaRest/api/v8/ws/tva",
+            HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""),
+        );
+        transport.respond(
+            Method::POST,
+            "/PlatitorTvaRest/api/v8/ws/tva",
+            HttpResponse::json(r#"{"cod":200,"message":"","found":[],"notFound":[111111115]}"#),
+        );
+
+        let client = AnafClient::builder()
+            .transport(transport.clone())
+            .without_rate_limit()
+            .build()
+            .unwrap();
+        let api = client.vat_payer(VatPayerApiVersion::V8);
+
+        let now = Utc::now().date_naive();
+        assert!(api
+            .send(vec![ApiRequest::new(Cui::new(111111115).unwrap(), now)])
+            .await
+            .is_err());
+
+        let response = api
+            .send(vec![ApiRequest::new(Cui::new(111111115).unwrap(), now)])
+            .await
+            .unwrap();
+        assert_eq!(response.not_found, vec![111111115]);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].body.as_deref(),
+            Some(format!(r#"[{{"cui":111111115,"data":"{}"}}]"#, now).as_bytes())
+        );
+    }
+
+    #[tokio::test]
+    async fn transport_rejects_unknown_routes() {
+        let client = AnafClient::builder()
+            .transport(InMemoryTransport::new())
+            .build()
+            .unwrap();
+
+        let request = vec![ApiRequest::new(
+            Cui::new(111111115).unwrap(),
+            Utc::now().date_naive(),
+        )];
+        let response = client.vat_payer(Default::default()).send(request).await;
+
+        assert!(response.is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use std::{fmt::Debug, future::Future, pin::Pin};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use serde::Serialize;
+
+use crate::Result;
+
+mod cassette;
+mod memory;
+mod reqwest_transport;
+
+pub use cassette::*;
+pub use memory::*;
+pub use reqwest_transport::*;
+
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<HttpResponse>> + Send + 'a>>;
+
+/// HTTP transport
+///
+/// Sends the HTTP requests of every API. [`ReqwestTransport`] is used by default, and
+/// [`InMemoryTransport`] serves canned responses in tests.
+/// [`CassetteTransport`] records real interactions and replays them.
+pub trait Transport: Debug + Send + Sync {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: &str) -> Self {
+        Self {
+            method,
+            url: url.to_owned(),
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(url: &str) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    /// Creates a POST request with a JSON body.
+    pub fn post_json<T: Serialize + ?Sized>(url: &str, body: &T) -> Result<Self> {
+        let mut request = Self::new(Method::POST, url);
+        request
+            .headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        request.body = Some(serde_json::to_vec(body)?);
+
+        Ok(request)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Creates a `200 OK` response with a JSON body.
+    pub fn json(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(StatusCode::OK, body).with_header(CONTENT_TYPE, "application/json")
+    }
+
+    pub fn with_header(mut self, name: HeaderName, value: &'static str) -> Self {
+        self.headers.insert(name, HeaderValue::from_static(value));
+        self
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}